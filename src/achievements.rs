@@ -0,0 +1,305 @@
+use crate::map::{TILE_SIZE, TileType};
+use crate::menu::LootSoldEvent;
+use crate::player::{Health, Player};
+use crate::stats::{LifetimeStats, RunStats, StatTotals};
+use bevy::prelude::*;
+use std::collections::HashSet;
+use std::fs;
+
+/// Achievement definitions shipped with the game
+const DEFINITIONS_FILE: &str = "assets/achievements.cfg";
+/// Ids of the unlocked achievements, next to the game like the other saves
+const UNLOCKED_FILE: &str = "achievements_unlocked.cfg";
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Achievements::load())
+            .add_event::<AchievementUnlockedEvent>()
+            .add_systems(Update, evaluate_achievements);
+    }
+}
+
+/// What has to happen for an achievement to unlock, compared against its threshold
+#[derive(Clone, Debug, PartialEq)]
+pub enum AchievementGoal {
+    Depth,
+    TilesDug(Option<TileType>),
+    DistanceFlown,
+    MoneyEarned,
+    BaseVisits,
+    // Landing at least this fast without being destroyed
+    LandingSpeed,
+    // Value sold in a single trip to the base, of one item or of all the cargo
+    SoldInOneTrip(Option<String>),
+}
+
+impl AchievementGoal {
+    /// Parses "depth", "tiles_dug", "tiles_dug:gold", "sold_in_trip:crystal"...
+    fn parse(text: &str) -> Option<Self> {
+        let (name, argument) = match text.split_once(':') {
+            Some((name, argument)) => (name, Some(argument)),
+            None => (text, None),
+        };
+        let goal = match (name, argument) {
+            ("depth", None) => AchievementGoal::Depth,
+            ("tiles_dug", None) => AchievementGoal::TilesDug(None),
+            ("tiles_dug", Some(tile)) => {
+                let tile_type = TileType::SOLID
+                    .into_iter()
+                    .find(|tile_type| format!("{:?}", tile_type).to_lowercase() == tile)?;
+                AchievementGoal::TilesDug(Some(tile_type))
+            }
+            ("distance_flown", None) => AchievementGoal::DistanceFlown,
+            ("money_earned", None) => AchievementGoal::MoneyEarned,
+            ("base_visits", None) => AchievementGoal::BaseVisits,
+            ("landing_speed", None) => AchievementGoal::LandingSpeed,
+            ("sold_in_trip", item) => AchievementGoal::SoldInOneTrip(item.map(str::to_string)),
+            _ => return None,
+        };
+        Some(goal)
+    }
+
+    /// Current value from the statistics, goals tied to single events have none
+    fn progress(&self, totals: &StatTotals) -> Option<f32> {
+        match self {
+            AchievementGoal::Depth => Some(totals.max_depth as f32),
+            AchievementGoal::TilesDug(None) => Some(totals.total_tiles_dug() as f32),
+            AchievementGoal::TilesDug(Some(tile_type)) => {
+                Some(totals.tiles_dug.get(tile_type).copied().unwrap_or(0) as f32)
+            }
+            AchievementGoal::DistanceFlown => Some(totals.distance_flown / TILE_SIZE),
+            AchievementGoal::MoneyEarned => Some(totals.money_earned as f32),
+            AchievementGoal::BaseVisits => Some(totals.base_visits as f32),
+            AchievementGoal::LandingSpeed => Some(totals.survived_landing),
+            AchievementGoal::SoldInOneTrip(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Achievement {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub goal: AchievementGoal,
+    pub threshold: f32,
+}
+
+#[derive(Event)]
+pub struct AchievementUnlockedEvent {
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Resource, Default)]
+pub struct Achievements {
+    pub definitions: Vec<Achievement>,
+    pub unlocked: HashSet<String>,
+}
+
+impl Achievements {
+    pub fn load() -> Self {
+        let mut achievements = Self::default();
+        match fs::read_to_string(DEFINITIONS_FILE) {
+            Ok(contents) => {
+                achievements.definitions = contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .filter_map(|line| {
+                        let achievement = parse_definition(line);
+                        if achievement.is_none() {
+                            warn!("Ignoring malformed achievement '{}'", line);
+                        }
+                        achievement
+                    })
+                    .collect();
+            }
+            Err(error) => warn!("Cannot read {}: {}", DEFINITIONS_FILE, error),
+        }
+        if let Ok(contents) = fs::read_to_string(UNLOCKED_FILE) {
+            achievements.unlocked = contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string)
+                .collect();
+        }
+        achievements
+    }
+
+    pub fn save(&self) {
+        let mut ids: Vec<&String> = self.unlocked.iter().collect();
+        ids.sort();
+        let mut contents = String::from("# Unlocked achievements\n");
+        for id in ids {
+            contents.push_str(id);
+            contents.push('\n');
+        }
+        if let Err(error) = fs::write(UNLOCKED_FILE, contents) {
+            warn!("Cannot save achievements to {}: {}", UNLOCKED_FILE, error);
+        }
+    }
+
+    /// One line per achievement, with the progress of the locked ones
+    pub fn describe(&self, totals: &StatTotals) -> Vec<String> {
+        self.definitions
+            .iter()
+            .map(|achievement| {
+                if self.unlocked.contains(&achievement.id) {
+                    return format!("[x] {}: {}", achievement.name, achievement.description);
+                }
+                match achievement.goal.progress(totals) {
+                    Some(progress) => format!(
+                        "[ ] {}: {} ({:.0}/{:.0})",
+                        achievement.name,
+                        achievement.description,
+                        progress.min(achievement.threshold),
+                        achievement.threshold
+                    ),
+                    None => format!("[ ] {}: {}", achievement.name, achievement.description),
+                }
+            })
+            .collect()
+    }
+
+    fn unlock(
+        &mut self,
+        index: usize,
+        unlocked_events: &mut EventWriter<AchievementUnlockedEvent>,
+    ) {
+        let achievement = &self.definitions[index];
+        info!("Achievement unlocked: {}", achievement.name);
+        unlocked_events.write(AchievementUnlockedEvent {
+            name: achievement.name.clone(),
+            description: achievement.description.clone(),
+        });
+        self.unlocked.insert(achievement.id.clone());
+        self.save();
+    }
+}
+
+/// Reads "id | name | description | goal | threshold"
+fn parse_definition(line: &str) -> Option<Achievement> {
+    let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+    let [id, name, description, goal, threshold] = fields.as_slice() else {
+        return None;
+    };
+    Some(Achievement {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        goal: AchievementGoal::parse(goal)?,
+        threshold: threshold.parse().ok()?,
+    })
+}
+
+/// Statistics of the finished runs plus the one being played
+pub fn combined_totals(run_stats: &RunStats, lifetime_stats: &LifetimeStats) -> StatTotals {
+    let mut totals = lifetime_stats.totals.clone();
+    if !run_stats.finished {
+        totals.add(&run_stats.totals);
+    }
+    totals
+}
+
+fn evaluate_achievements(
+    player: Query<&Health, With<Player>>,
+    run_stats: Res<RunStats>,
+    lifetime_stats: Res<LifetimeStats>,
+    mut loot_sold_events: EventReader<LootSoldEvent>,
+    mut achievements: ResMut<Achievements>,
+    mut unlocked_events: EventWriter<AchievementUnlockedEvent>,
+) {
+    // Nothing is earned by the blow that destroys the drill
+    if player.iter().any(|health| health.current <= 0.0) {
+        return;
+    }
+    let totals = combined_totals(&run_stats, &lifetime_stats);
+    let sales: Vec<&LootSoldEvent> = loot_sold_events.read().collect();
+    for index in 0..achievements.definitions.len() {
+        let achievement = &achievements.definitions[index];
+        if achievements.unlocked.contains(&achievement.id) {
+            continue;
+        }
+        let reached = match &achievement.goal {
+            AchievementGoal::SoldInOneTrip(item_id) => sales.iter().any(|sale| {
+                let value: u32 = sale
+                    .items
+                    .iter()
+                    .filter(|item| item_id.as_ref().is_none_or(|id| *id == item.id))
+                    .map(|item| item.value * item.quantity as u32)
+                    .sum();
+                value as f32 >= achievement.threshold
+            }),
+            goal => goal
+                .progress(&totals)
+                .is_some_and(|progress| progress >= achievement.threshold),
+        };
+        if reached {
+            achievements.unlock(index, &mut unlocked_events);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_goals_with_and_without_arguments() {
+        assert_eq!(AchievementGoal::parse("depth"), Some(AchievementGoal::Depth));
+        assert_eq!(
+            AchievementGoal::parse("tiles_dug:gold"),
+            Some(AchievementGoal::TilesDug(Some(TileType::Gold)))
+        );
+        assert_eq!(
+            AchievementGoal::parse("sold_in_trip:crystal"),
+            Some(AchievementGoal::SoldInOneTrip(Some("crystal".to_string())))
+        );
+        assert_eq!(
+            AchievementGoal::parse("sold_in_trip"),
+            Some(AchievementGoal::SoldInOneTrip(None))
+        );
+        assert_eq!(AchievementGoal::parse("tiles_dug:empty"), None);
+        assert_eq!(AchievementGoal::parse("depth:5"), None);
+        assert_eq!(AchievementGoal::parse("flying"), None);
+    }
+
+    #[test]
+    fn parses_a_definition() {
+        let line = "abyss | Into the Abyss | Reach depth 450 | depth | 450";
+        let achievement = parse_definition(line).unwrap();
+        assert_eq!(achievement.id, "abyss");
+        assert_eq!(achievement.name, "Into the Abyss");
+        assert_eq!(achievement.description, "Reach depth 450");
+        assert_eq!(achievement.goal, AchievementGoal::Depth);
+        assert_eq!(achievement.threshold, 450.0);
+        assert!(parse_definition("abyss | Into the Abyss | depth | 450").is_none());
+        assert!(parse_definition("abyss | Into the Abyss | Reach depth | depth | deep").is_none());
+    }
+
+    #[test]
+    fn shipped_definitions_all_parse() {
+        let contents = fs::read_to_string(DEFINITIONS_FILE).unwrap();
+        for line in contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        {
+            assert!(parse_definition(line).is_some(), "cannot parse '{}'", line);
+        }
+    }
+
+    #[test]
+    fn landing_goal_ignores_the_fatal_landing() {
+        let totals = StatTotals {
+            fastest_landing: 900.0,
+            survived_landing: 300.0,
+            ..default()
+        };
+        assert_eq!(AchievementGoal::LandingSpeed.progress(&totals), Some(300.0));
+    }
+}
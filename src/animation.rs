@@ -1,11 +1,15 @@
 use crate::BlackQuartzCamera;
 use crate::game::GameSystems::Animation;
 use crate::prelude::*;
-use crate::player::PlayerImpactEvent;
+use crate::player::{
+    ClawArm, DamageEvent, DrillBlockedEvent, Dynamite, Explosion, GrappleRope, LaserBeam, ToolBelt,
+    ToolState,
+};
 use bevy::app::App;
 use bevy::prelude::{
-    Commands, Component, Entity, EventReader, IntoScheduleConfigs, Plugin, Query, Res, Time,
-    Timer, TimerMode, Transform, Update, Vec3, With, debug,
+    Alpha, Color, Commands, Component, Entity, EventReader, IntoScheduleConfigs, Local, Plugin,
+    Quat, Query, Res, Sprite, Time, Timer, TimerMode, Transform, Update, Vec2, Vec3, With,
+    Without, debug, default,
 };
 use std::time::Duration;
 use rand::Rng;
@@ -16,10 +20,26 @@ impl Plugin for GameAnimationPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Update, animate_drilling.in_set(Animation))
             .add_systems(Update, animate_camera.in_set(Animation))
-            .add_systems(Update, handle_camera_shake.in_set(Animation));
+            .add_systems(Update, handle_camera_shake.in_set(Animation))
+            .add_systems(Update, (spawn_drill_sparks, animate_sparks).in_set(Animation))
+            .add_systems(
+                Update,
+                (
+                    animate_dynamite,
+                    animate_explosions,
+                    animate_laser_beam,
+                    animate_claw,
+                    animate_grapple_rope,
+                )
+                    .in_set(Animation),
+            );
     }
 }
 
+const SPARK_BURST_INTERVAL: f32 = 0.15;
+const SPARKS_PER_BURST: usize = 6;
+const SPARK_GRAVITY: f32 = 400.0;
+
 #[derive(Component)]
 #[require(DrillShake)]
 pub struct DrillAnimation {
@@ -54,6 +74,12 @@ impl Default for DrillShake {
     }
 }
 
+#[derive(Component)]
+struct Spark {
+    velocity: Vec2,
+    timer: Timer,
+}
+
 #[derive(Component, Debug, Clone)]
 pub struct CameraShake {
     pub base_position: Option<Vec3>,
@@ -63,10 +89,10 @@ pub struct CameraShake {
 
 fn handle_camera_shake(
     mut commands: Commands,
-    mut events: EventReader<PlayerImpactEvent>,
+    mut events: EventReader<DamageEvent>,
     camera: Query<Entity, With<BlackQuartzCamera>>,
 ) {
-    for _ in events.read() {
+    for _ in events.read().filter(|event| event.damage_type.is_hit()) {
         for entity in camera.iter() {
             commands.entity(entity).insert(CameraShake {
                 base_position: None,
@@ -175,3 +201,216 @@ fn animate_camera(
         }
     }
 }
+
+fn spawn_drill_sparks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut events: EventReader<DrillBlockedEvent>,
+    mut since_last_burst: Local<f32>,
+) {
+    *since_last_burst += time.delta_secs();
+    let Some(event) = events.read().last() else {
+        return;
+    };
+    if *since_last_burst < SPARK_BURST_INTERVAL {
+        return;
+    }
+    *since_last_burst = 0.0;
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..SPARKS_PER_BURST {
+        let velocity = Vec2::new(rng.gen_range(-80.0..80.0), rng.gen_range(20.0..120.0));
+        commands.spawn((
+            Spark {
+                velocity,
+                timer: Timer::from_seconds(rng.gen_range(0.2..0.4), TimerMode::Once),
+            },
+            Sprite {
+                color: Color::srgb(1.0, 0.85, 0.3),
+                custom_size: Some(Vec2::splat(2.0)),
+                ..default()
+            },
+            Transform::from_translation(event.contact_point.extend(2.0)),
+        ));
+    }
+}
+
+fn animate_sparks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut sparks: Query<(Entity, &mut Spark, &mut Transform, &mut Sprite)>,
+) {
+    let dt = time.delta_secs();
+    for (entity, mut spark, mut transform, mut sprite) in &mut sparks {
+        spark.timer.tick(time.delta());
+        if spark.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        spark.velocity.y -= SPARK_GRAVITY * dt;
+        transform.translation += (spark.velocity * dt).extend(0.0);
+        sprite.color.set_alpha(spark.timer.fraction_remaining());
+    }
+}
+
+fn animate_dynamite(mut dynamites: Query<(&Dynamite, &mut Sprite)>) {
+    for (dynamite, mut sprite) in &mut dynamites {
+        // The fuse blinks faster and faster as it burns out
+        let elapsed = dynamite.fuse.elapsed_secs();
+        let blink_frequency = 2.0 + 10.0 * dynamite.fuse.fraction();
+        sprite.color = if (elapsed * blink_frequency).fract() < 0.5 {
+            Color::srgb(0.85, 0.1, 0.1)
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+fn animate_explosions(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut explosions: Query<(Entity, &mut Explosion, &mut Sprite)>,
+) {
+    for (entity, mut explosion, mut sprite) in &mut explosions {
+        explosion.timer.tick(time.delta());
+        if explosion.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let progress = explosion.timer.fraction();
+        sprite.custom_size = Some(Vec2::splat(explosion.radius * 2.0 * progress.max(0.2)));
+        sprite.color.set_alpha(0.9 * (1.0 - progress));
+    }
+}
+
+type LaserBeamFilter = (With<LaserBeam>, Without<Player>);
+
+fn animate_laser_beam(
+    mut commands: Commands,
+    time: Res<Time>,
+    player: Query<(Entity, &Transform, &ToolState, &ToolBelt), With<Player>>,
+    mut beams: Query<(Entity, &LaserBeam, &mut Transform, &mut Sprite), LaserBeamFilter>,
+) {
+    // Beams whose drill stopped firing (or is gone) fade out at once
+    for (entity, beam, _, _) in &beams {
+        let firing = player.get(beam.owner).is_ok_and(|(_, _, tool_state, tool_belt)| {
+            *tool_state == ToolState::Lasering && tool_belt.laser_hit.is_some()
+        });
+        if !firing {
+            commands.entity(entity).despawn();
+        }
+    }
+    for (player_entity, player_transform, tool_state, tool_belt) in player.iter() {
+        let hit = tool_belt
+            .laser_hit
+            .filter(|_| *tool_state == ToolState::Lasering);
+        let Some(hit) = hit else {
+            continue;
+        };
+
+        let origin = player_transform.translation.truncate();
+        let beam = hit - origin;
+        let transform = Transform::from_translation((origin + beam / 2.0).extend(2.0))
+            .with_rotation(Quat::from_rotation_z(beam.y.atan2(beam.x)));
+        // Flickering width and brightness
+        let flicker = (time.elapsed_secs() * 40.0).sin() * 0.5 + 0.5;
+        let size = Vec2::new(beam.length(), 2.0 + flicker);
+        let color = Color::srgba(1.0, 0.2, 0.2, 0.6 + 0.4 * flicker);
+
+        if let Some((_, _, mut beam_transform, mut sprite)) = beams
+            .iter_mut()
+            .find(|(_, beam, _, _)| beam.owner == player_entity)
+        {
+            *beam_transform = transform;
+            sprite.custom_size = Some(size);
+            sprite.color = color;
+        } else {
+            commands.spawn((
+                LaserBeam {
+                    owner: player_entity,
+                },
+                Sprite {
+                    color,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform,
+            ));
+        }
+    }
+}
+
+type GrappleRopeFilter = (With<GrappleRope>, Without<Player>);
+
+fn animate_grapple_rope(
+    mut commands: Commands,
+    player: Query<(Entity, &Transform, &ToolBelt), With<Player>>,
+    mut ropes: Query<(Entity, &GrappleRope, &mut Transform, &mut Sprite), GrappleRopeFilter>,
+) {
+    for (entity, rope, _, _) in &ropes {
+        let attached = player
+            .get(rope.owner)
+            .is_ok_and(|(_, _, tool_belt)| tool_belt.grapple.is_some());
+        if !attached {
+            commands.entity(entity).despawn();
+        }
+    }
+    for (player_entity, player_transform, tool_belt) in player.iter() {
+        let Some(anchor) = tool_belt.grapple else {
+            continue;
+        };
+
+        let origin = player_transform.translation.truncate();
+        let rope = anchor.point - origin;
+        let transform = Transform::from_translation((origin + rope / 2.0).extend(1.5))
+            .with_rotation(Quat::from_rotation_z(rope.y.atan2(rope.x)));
+        let size = Vec2::new(rope.length(), 1.5);
+
+        if let Some((_, _, mut rope_transform, mut sprite)) = ropes
+            .iter_mut()
+            .find(|(_, rope, _, _)| rope.owner == player_entity)
+        {
+            *rope_transform = transform;
+            sprite.custom_size = Some(size);
+        } else {
+            commands.spawn((
+                GrappleRope {
+                    owner: player_entity,
+                },
+                Sprite {
+                    color: Color::srgb(0.55, 0.45, 0.3),
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform,
+            ));
+        }
+    }
+}
+
+fn animate_claw(
+    mut commands: Commands,
+    time: Res<Time>,
+    player: Query<&Transform, With<Player>>,
+    mut claws: Query<(Entity, &mut ClawArm, &mut Transform, &mut Sprite), Without<Player>>,
+) {
+    for (entity, mut claw, mut transform, mut sprite) in &mut claws {
+        claw.timer.tick(time.delta());
+        let Ok(player_transform) = player.get(claw.owner) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        if claw.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        // The arm reaches out for the first half of the animation and retracts in the second
+        let origin = player_transform.translation.truncate();
+        let progress = claw.timer.fraction();
+        let extension = 1.0 - (progress * 2.0 - 1.0).abs();
+        let reach = (claw.target - origin) * extension;
+        transform.translation = (origin + reach / 2.0).extend(1.5);
+        transform.rotation = Quat::from_rotation_z(reach.y.atan2(reach.x));
+        sprite.custom_size = Some(Vec2::new(reach.length().max(1.0), 4.0));
+    }
+}
@@ -28,7 +28,7 @@ fn setup_camera(mut commands: Commands, mut loading_progress: ResMut<LoadingProg
             far: 1000.0,
             viewport_origin: Vec2::new(0.5, 0.5),
             scaling_mode: Default::default(),
-            scale: 0.45,
+            scale: CAMERA_SCALE,
             area: Default::default(),
         }),
         BlackQuartzCamera,
@@ -36,34 +36,56 @@ fn setup_camera(mut commands: Commands, mut loading_progress: ResMut<LoadingProg
     loading_progress.init_camera = true;
 }
 
+/// Zoom of a single drill; co-op zooms out up to `MAX_CAMERA_SCALE` to keep every drill in view
+const CAMERA_SCALE: f32 = 0.45;
+const MAX_CAMERA_SCALE: f32 = 0.9;
+/// Room (px) kept between the outermost drill and the screen edge
+const CAMERA_MARGIN: f32 = 64.0;
+
 fn follow_player(
     time: Res<Time>,
     query_player: Query<(&Transform, &DrillState), With<Player>>,
     mut query_camera: Query<
-        (&mut Transform, &Projection),
+        (&mut Transform, &mut Projection),
         (With<BlackQuartzCamera>, Without<Player>),
     >,
     world_grid: Res<WorldGrid>,
 ) {
-    // Camera handling
-    if let Ok((player_transform, _drill_state)) = query_player.single() {
-        let player_pos = player_transform.translation;
-        if let Ok((mut camera_pos, camera)) = query_camera.single_mut() {
-            if let Projection::Orthographic(ortho) = camera {
-                let camera_area = ortho.area;
-                let t = (5.0_f32 * time.delta_secs()).min(1.0_f32);
+    // Camera handling: frame the box around every drill
+    let mut positions = query_player
+        .iter()
+        .map(|(transform, _drill_state)| transform.translation.truncate());
+    let Some(first) = positions.next() else {
+        return;
+    };
+    let (min, max) = positions.fold((first, first), |(min, max), position| {
+        (min.min(position), max.max(position))
+    });
+    let player_pos = (min + max) / 2.0;
+    if let Ok((mut camera_pos, mut camera)) = query_camera.single_mut() {
+        if let Projection::Orthographic(ortho) = &mut *camera {
+            let camera_area = ortho.area;
+            let t = (5.0_f32 * time.delta_secs()).min(1.0_f32);
+
+            // The visible area grows with the scale, so zoom out until the spread fits
+            let half_spread = (max - min) / 2.0 + CAMERA_MARGIN;
+            let half_area = camera_area.half_size() / ortho.scale;
+            if half_area.min_element() > 0.0 {
+                let needed = (half_spread / half_area).max_element();
+                let target_scale = needed.clamp(CAMERA_SCALE, MAX_CAMERA_SCALE);
+                ortho.scale = ortho.scale.lerp(target_scale, t);
+            }
 
-                if player_pos.x + camera_area.max.x <= world_grid.map_area.max.x
-                    && player_pos.x + camera_area.min.x >= world_grid.map_area.min.x
-                {
-                    camera_pos.translation.x = camera_pos.translation.x.lerp(player_pos.x, t);
-                }
+            if player_pos.x + camera_area.max.x <= world_grid.map_area.max.x
+                && player_pos.x + camera_area.min.x >= world_grid.map_area.min.x
+            {
+                camera_pos.translation.x = camera_pos.translation.x.lerp(player_pos.x, t);
+            }
 
-                if player_pos.y + camera_area.max.y <= world_grid.map_area.max.y
-                    && player_pos.y + camera_area.min.y >= world_grid.map_area.min.y
-                {
-                    camera_pos.translation.y = camera_pos.translation.y.lerp(player_pos.y, t);
-                }
+            if player_pos.y + camera_area.max.y <= world_grid.map_area.max.y
+                && player_pos.y + camera_area.min.y >= world_grid.map_area.min.y
+            {
+                camera_pos.translation.y = camera_pos.translation.y.lerp(player_pos.y, t);
             }
         }
     }
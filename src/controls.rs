@@ -0,0 +1,608 @@
+use crate::game::CoopConfig;
+use crate::network::RemoteInput;
+use crate::player::{DamageEvent, Player, PlayerId};
+use bevy::input::InputSystem;
+use bevy::input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest};
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::time::Duration;
+
+/// Files the key bindings of each local player are loaded from and saved to, next to the game
+const BINDINGS_FILES: [&str; 2] = ["controls.cfg", "controls_p2.cfg"];
+/// Stick and trigger travel ignored before they register at all
+const ANALOG_DEAD_ZONE: f32 = 0.2;
+/// Stick and trigger travel past which the digital action counts as pressed
+const ANALOG_PRESS_THRESHOLD: f32 = 0.5;
+const RUMBLE_SECS: f32 = 0.2;
+/// Damage of a hit that rumbles at full strength
+const RUMBLE_FULL_DAMAGE: f32 = 40.0;
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PlayerBindings::load())
+            .init_resource::<ButtonInput<Action>>()
+            .init_resource::<ActionAxes>()
+            .add_systems(PreUpdate, update_actions.after(InputSystem))
+            .add_systems(Update, rumble_on_hits);
+    }
+}
+
+/// What the player wants to do, gameplay systems read these instead of the physical keys
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Thrust,
+    DrillDown,
+    UseTool,
+    NextTool,
+    Scan,
+    UseItem(usize),
+    Map,
+    PlaceMarker,
+    ZoomIn,
+    ZoomOut,
+    Recall,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 18] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Thrust,
+        Action::DrillDown,
+        Action::UseTool,
+        Action::NextTool,
+        Action::Scan,
+        Action::UseItem(0),
+        Action::UseItem(1),
+        Action::UseItem(2),
+        Action::UseItem(3),
+        Action::UseItem(4),
+        Action::Map,
+        Action::PlaceMarker,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::Recall,
+        Action::Pause,
+    ];
+
+    /// Name used in the bindings file
+    pub fn id(&self) -> String {
+        match self {
+            Action::MoveLeft => "move_left".to_string(),
+            Action::MoveRight => "move_right".to_string(),
+            Action::Thrust => "thrust".to_string(),
+            Action::DrillDown => "drill_down".to_string(),
+            Action::UseTool => "use_tool".to_string(),
+            Action::NextTool => "next_tool".to_string(),
+            Action::Scan => "scan".to_string(),
+            Action::UseItem(slot) => format!("use_item_{}", slot + 1),
+            Action::Map => "map".to_string(),
+            Action::PlaceMarker => "place_marker".to_string(),
+            Action::ZoomIn => "zoom_in".to_string(),
+            Action::ZoomOut => "zoom_out".to_string(),
+            Action::Recall => "recall".to_string(),
+            Action::Pause => "pause".to_string(),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Action::MoveLeft => "Move left".to_string(),
+            Action::MoveRight => "Move right".to_string(),
+            Action::Thrust => "Thrust".to_string(),
+            Action::DrillDown => "Drill down".to_string(),
+            Action::UseTool => "Use tool".to_string(),
+            Action::NextTool => "Next tool".to_string(),
+            Action::Scan => "Scan".to_string(),
+            Action::UseItem(slot) => format!("Use item {}", slot + 1),
+            Action::Map => "Map".to_string(),
+            Action::PlaceMarker => "Place marker".to_string(),
+            Action::ZoomIn => "Zoom in".to_string(),
+            Action::ZoomOut => "Zoom out".to_string(),
+            Action::Recall => "Recall".to_string(),
+            Action::Pause => "Pause".to_string(),
+        }
+    }
+
+    /// The second player gets the keys on the right of the keyboard and no menu keys, the menus
+    /// listen to everyone
+    fn default_keys(&self, player: usize) -> Vec<KeyCode> {
+        if player > 0 {
+            return match self {
+                Action::MoveLeft => vec![KeyCode::KeyJ],
+                Action::MoveRight => vec![KeyCode::KeyL],
+                Action::Thrust => vec![KeyCode::KeyI],
+                Action::DrillDown => vec![KeyCode::KeyK],
+                Action::UseTool => vec![KeyCode::Enter],
+                Action::NextTool => vec![KeyCode::KeyU],
+                Action::Scan => vec![KeyCode::KeyO],
+                Action::UseItem(slot) => vec![BINDABLE_NUMPAD_DIGITS[*slot]],
+                Action::Recall => vec![KeyCode::KeyP],
+                Action::Map
+                | Action::PlaceMarker
+                | Action::ZoomIn
+                | Action::ZoomOut
+                | Action::Pause => vec![],
+            };
+        }
+        match self {
+            Action::MoveLeft => vec![KeyCode::ArrowLeft, KeyCode::KeyA],
+            Action::MoveRight => vec![KeyCode::ArrowRight, KeyCode::KeyD],
+            Action::Thrust => vec![KeyCode::ArrowUp, KeyCode::KeyW],
+            Action::DrillDown => vec![KeyCode::ArrowDown, KeyCode::KeyS],
+            Action::UseTool => vec![KeyCode::Space],
+            Action::NextTool => vec![KeyCode::KeyQ],
+            Action::Scan => vec![KeyCode::KeyE],
+            Action::UseItem(slot) => vec![BINDABLE_DIGITS[*slot]],
+            Action::Map => vec![KeyCode::KeyM],
+            Action::PlaceMarker => vec![KeyCode::KeyN],
+            Action::ZoomIn => vec![KeyCode::Equal],
+            Action::ZoomOut => vec![KeyCode::Minus],
+            Action::Recall => vec![KeyCode::KeyR],
+            Action::Pause => vec![KeyCode::Escape],
+        }
+    }
+
+    /// Gamepad layout, the sticks and the right trigger are read separately as analog input.
+    /// The triggers belong to the hotbar, the full map zooms with the right stick
+    pub fn gamepad_buttons(&self) -> Vec<GamepadButton> {
+        match self {
+            Action::MoveLeft => vec![GamepadButton::DPadLeft],
+            Action::MoveRight => vec![GamepadButton::DPadRight],
+            Action::Thrust => vec![GamepadButton::DPadUp],
+            Action::DrillDown => vec![GamepadButton::DPadDown],
+            Action::UseTool => vec![GamepadButton::South],
+            Action::NextTool => vec![GamepadButton::West],
+            Action::Scan => vec![GamepadButton::LeftThumb],
+            Action::UseItem(0) => vec![GamepadButton::North],
+            Action::UseItem(1) => vec![GamepadButton::East],
+            Action::UseItem(2) => vec![GamepadButton::LeftTrigger],
+            Action::UseItem(3) => vec![GamepadButton::RightTrigger],
+            Action::UseItem(4) => vec![GamepadButton::LeftTrigger2],
+            Action::UseItem(_) => vec![],
+            Action::Map => vec![GamepadButton::Select],
+            Action::PlaceMarker => vec![GamepadButton::RightThumb],
+            Action::ZoomIn | Action::ZoomOut => vec![],
+            Action::Recall => vec![GamepadButton::Mode],
+            Action::Pause => vec![GamepadButton::Start],
+        }
+    }
+}
+
+/// Analog strength of the movement actions, from 0 to 1 (-1 to 1 for the horizontal axis).
+/// Keys and the d-pad always give full strength
+#[derive(Resource, Default, Clone, Copy)]
+pub struct ActionAxes {
+    pub horizontal: f32,
+    pub thrust: f32,
+}
+
+/// Actions of one local player, gameplay systems read these instead of the global
+/// `ButtonInput<Action>`, which merges every player for the menus
+#[derive(Component, Default)]
+pub struct PlayerActions {
+    pub buttons: ButtonInput<Action>,
+    pub axes: ActionAxes,
+}
+
+/// Rescales the travel past the dead zone to the full 0..1 range
+fn apply_dead_zone(value: f32) -> f32 {
+    if value.abs() < ANALOG_DEAD_ZONE {
+        0.0
+    } else {
+        value.signum() * (value.abs() - ANALOG_DEAD_ZONE) / (1.0 - ANALOG_DEAD_ZONE)
+    }
+}
+
+const BINDABLE_NUMPAD_DIGITS: [KeyCode; 5] = [
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+];
+
+const BINDABLE_DIGITS: [KeyCode; 10] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Digit0,
+];
+
+/// Keys that can be bound and written to the bindings file
+const BINDABLE_KEYS: [KeyCode; 59] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+];
+
+pub fn is_bindable(key: KeyCode) -> bool {
+    BINDABLE_KEYS.contains(&key) || BINDABLE_DIGITS.contains(&key)
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    // Escape cancels a rebind so it cannot be picked, but the default pause key is still saved
+    BINDABLE_KEYS
+        .into_iter()
+        .chain(BINDABLE_DIGITS)
+        .chain([KeyCode::Escape])
+        .find(|key| format!("{:?}", key) == name)
+}
+
+/// Short label for the HUD and the settings menu, "KeyW" becomes "W"
+pub fn key_label(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    match name.strip_prefix("Key").or(name.strip_prefix("Digit")) {
+        Some(short) => short.to_string(),
+        None => name,
+    }
+}
+
+/// Keys bound to each action, an action can have more than one
+pub struct KeyBindings {
+    pub bindings: HashMap<Action, Vec<KeyCode>>,
+}
+
+impl KeyBindings {
+    pub fn default_for(player: usize) -> Self {
+        Self {
+            bindings: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_keys(player)))
+                .collect(),
+        }
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.bindings.get(&action).map_or(&[], |keys| keys.as_slice())
+    }
+
+    /// Keys of the action joined for display, e.g. "ArrowUp / W"
+    pub fn describe(&self, action: Action) -> String {
+        let keys = self.keys(action);
+        if keys.is_empty() {
+            return "-".to_string();
+        }
+        keys.iter()
+            .map(|key| key_label(*key))
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+
+    /// Binds the key to the action alone, taking it away from any other action
+    pub fn rebind(&mut self, action: Action, key: KeyCode) {
+        for keys in self.bindings.values_mut() {
+            keys.retain(|bound| *bound != key);
+        }
+        self.bindings.insert(action, vec![key]);
+    }
+
+    /// Starts from the defaults so actions missing from the file keep their keys
+    pub fn load(player: usize) -> Self {
+        let file = BINDINGS_FILES[player];
+        let Ok(contents) = fs::read_to_string(file) else {
+            info!("No {} found, using the default key bindings", file);
+            return Self::default_for(player);
+        };
+        Self::parse(player, &contents)
+    }
+
+    fn parse(player: usize, contents: &str) -> Self {
+        let mut key_bindings = Self::default_for(player);
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((id, keys)) = line.split_once('=') else {
+                warn!("Ignoring malformed binding '{}'", line);
+                continue;
+            };
+            let Some(action) = Action::ALL.into_iter().find(|a| a.id() == id.trim()) else {
+                warn!("Ignoring binding for unknown action '{}'", id.trim());
+                continue;
+            };
+            let keys: Vec<KeyCode> = keys
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .filter_map(|name| {
+                    let key = parse_key(name);
+                    if key.is_none() {
+                        warn!("Ignoring unknown key '{}' for {}", name, action.id());
+                    }
+                    key
+                })
+                .collect();
+            key_bindings.bindings.insert(action, keys);
+        }
+        key_bindings
+    }
+
+    pub fn save(&self, player: usize) {
+        let file = BINDINGS_FILES[player];
+        match fs::write(file, self.to_cfg()) {
+            Ok(()) => info!("Key bindings saved to {}", file),
+            Err(error) => warn!("Cannot save key bindings to {}: {}", file, error),
+        }
+    }
+
+    fn to_cfg(&self) -> String {
+        let mut contents = String::from("# Key bindings, one action per line: action = Key, Key\n");
+        for action in Action::ALL {
+            let keys = self
+                .keys(action)
+                .iter()
+                .map(|key| format!("{:?}", key))
+                .collect::<Vec<_>>()
+                .join(", ");
+            contents.push_str(&format!("{} = {}\n", action.id(), keys));
+        }
+        contents
+    }
+}
+
+/// Key bindings of each local player, indexed by `PlayerId`
+#[derive(Resource)]
+pub struct PlayerBindings {
+    pub players: Vec<KeyBindings>,
+}
+
+impl PlayerBindings {
+    pub fn load() -> Self {
+        Self {
+            players: (0..BINDINGS_FILES.len()).map(KeyBindings::load).collect(),
+        }
+    }
+
+    pub fn get(&self, player: usize) -> &KeyBindings {
+        &self.players[player]
+    }
+}
+
+/// Gamepads are handed out in connection order, the extra ones drive the last player
+fn gamepad_player(gamepad_index: usize, players: usize) -> usize {
+    gamepad_index.min(players.max(1) - 1)
+}
+
+/// Mirrors the keyboard and the gamepads into each player's `PlayerActions`, and all of them
+/// together into `ButtonInput<Action>` and `ActionAxes`. Drills of online clients are fed by the host
+#[allow(clippy::type_complexity)]
+pub fn update_actions(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    player_bindings: Res<PlayerBindings>,
+    coop: Res<CoopConfig>,
+    mut actions: ResMut<ButtonInput<Action>>,
+    mut axes: ResMut<ActionAxes>,
+    mut players: Query<(&PlayerId, &mut PlayerActions), (With<Player>, Without<RemoteInput>)>,
+) {
+    let all_gamepads: Vec<&Gamepad> = gamepads.iter().collect();
+    read_actions(
+        &keyboard_input,
+        &all_gamepads,
+        &player_bindings.players.iter().collect::<Vec<_>>(),
+        &mut actions,
+        &mut axes,
+    );
+    for (player_id, mut player_actions) in players.iter_mut() {
+        let player_gamepads: Vec<&Gamepad> = all_gamepads
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| gamepad_player(*index, coop.players) == player_id.0)
+            .map(|(_, gamepad)| *gamepad)
+            .collect();
+        let player_actions = &mut *player_actions;
+        read_actions(
+            &keyboard_input,
+            &player_gamepads,
+            &[player_bindings.get(player_id.0)],
+            &mut player_actions.buttons,
+            &mut player_actions.axes,
+        );
+    }
+}
+
+fn read_actions(
+    keyboard_input: &ButtonInput<KeyCode>,
+    gamepads: &[&Gamepad],
+    key_bindings: &[&KeyBindings],
+    actions: &mut ButtonInput<Action>,
+    axes: &mut ActionAxes,
+) {
+    let stick = gamepads
+        .iter()
+        .map(|gamepad| gamepad.left_stick())
+        .map(|stick| Vec2::new(apply_dead_zone(stick.x), apply_dead_zone(stick.y)))
+        .find(|stick| *stick != Vec2::ZERO)
+        .unwrap_or(Vec2::ZERO);
+    let trigger = gamepads
+        .iter()
+        .filter_map(|gamepad| gamepad.get(GamepadButton::RightTrigger2))
+        .map(apply_dead_zone)
+        .fold(0.0, f32::max);
+    let analog = |action: Action| match action {
+        Action::MoveLeft => (-stick.x).max(0.0),
+        Action::MoveRight => stick.x.max(0.0),
+        Action::Thrust => trigger.max(stick.y),
+        Action::DrillDown => (-stick.y).max(0.0),
+        _ => 0.0,
+    };
+    let buttons_pressed = |action: Action| {
+        key_bindings
+            .iter()
+            .any(|bindings| keyboard_input.any_pressed(bindings.keys(action).iter().copied()))
+            || gamepads
+                .iter()
+                .any(|gamepad| gamepad.any_pressed(action.gamepad_buttons()))
+    };
+
+    actions.clear();
+    for action in Action::ALL {
+        let pressed = buttons_pressed(action) || analog(action) > ANALOG_PRESS_THRESHOLD;
+        if pressed && !actions.pressed(action) {
+            actions.press(action);
+        } else if !pressed && actions.pressed(action) {
+            actions.release(action);
+        }
+    }
+
+    let strength = |action: Action| {
+        if buttons_pressed(action) {
+            1.0
+        } else {
+            analog(action)
+        }
+    };
+    axes.horizontal = strength(Action::MoveRight) - strength(Action::MoveLeft);
+    axes.thrust = strength(Action::Thrust);
+}
+
+/// Shakes the gamepads of the player hit, harder hits rumble stronger
+fn rumble_on_hits(
+    mut events: EventReader<DamageEvent>,
+    gamepads: Query<Entity, With<Gamepad>>,
+    players: Query<&PlayerId, With<Player>>,
+    coop: Res<CoopConfig>,
+    mut rumble_requests: EventWriter<GamepadRumbleRequest>,
+) {
+    for event in events.read().filter(|event| event.damage_type.is_hit()) {
+        let Ok(player_id) = players.get(event.player) else {
+            continue;
+        };
+        let strength = (event.amount / RUMBLE_FULL_DAMAGE).clamp(0.2, 1.0);
+        for (_, gamepad) in gamepads
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| gamepad_player(*index, coop.players) == player_id.0)
+        {
+            rumble_requests.write(GamepadRumbleRequest::Add {
+                duration: Duration::from_secs_f32(RUMBLE_SECS),
+                intensity: GamepadRumbleIntensity {
+                    strong_motor: strength,
+                    weak_motor: strength * 0.5,
+                },
+                gamepad,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_round_trip() {
+        let mut bindings = KeyBindings::default_for(0);
+        bindings.rebind(Action::Scan, KeyCode::KeyF);
+        bindings.rebind(Action::UseTool, KeyCode::KeyE);
+        let loaded = KeyBindings::parse(0, &bindings.to_cfg());
+        for action in Action::ALL {
+            assert_eq!(loaded.keys(action), bindings.keys(action), "{:?}", action);
+        }
+    }
+
+    #[test]
+    fn rebinding_takes_the_key_from_other_actions() {
+        let mut bindings = KeyBindings::default_for(0);
+        bindings.rebind(Action::Scan, KeyCode::KeyQ);
+        assert_eq!(bindings.keys(Action::Scan), [KeyCode::KeyQ]);
+        assert!(!bindings.keys(Action::NextTool).contains(&KeyCode::KeyQ));
+    }
+
+    #[test]
+    fn missing_and_unknown_entries_keep_the_defaults() {
+        let bindings = KeyBindings::parse(1, "scan = KeyX, Bogus\nfly = KeyZ\nnonsense\n");
+        assert_eq!(bindings.keys(Action::Scan), [KeyCode::KeyX]);
+        assert_eq!(
+            bindings.keys(Action::Thrust),
+            Action::Thrust.default_keys(1).as_slice()
+        );
+    }
+
+    #[test]
+    fn escape_is_saved_but_not_bindable() {
+        assert!(!is_bindable(KeyCode::Escape));
+        let bindings = KeyBindings::parse(0, &KeyBindings::default_for(0).to_cfg());
+        assert_eq!(bindings.keys(Action::Pause), [KeyCode::Escape]);
+    }
+
+    #[test]
+    fn no_gamepad_button_triggers_two_actions() {
+        let mut seen = Vec::new();
+        for action in Action::ALL {
+            for button in action.gamepad_buttons() {
+                assert!(!seen.contains(&button), "{:?} is bound twice", button);
+                seen.push(button);
+            }
+        }
+    }
+}
@@ -33,10 +33,36 @@ pub enum GameState {
 pub struct EconomyConfig {
     pub fuel_price_per_unit: u32,
     pub fuel_refill_amount: f32,
-    pub upgrade_drill_cost: u32,
-    pub upgrade_speed_cost: u32,
-    pub upgrade_tank_cost: u32,
-    pub upgrade_armor_cost: u32,
+    pub dynamite_cost: u32,
+    pub repair_kit_cost: u32,
+    pub fuel_canister_cost: u32,
+    pub teleporter_cost: u32,
+    pub shield_cost: u32,
+    pub overclock_cost: u32,
+    pub recall_device_cost: u32,
+    pub repair_cost_per_point: u32,
+    pub tow_base_cost: u32,
+    pub tow_cost_per_depth: f32,
+    pub tow_cost_per_kg: f32,
+}
+
+impl EconomyConfig {
+    /// Towing gets pricier the deeper the drill is and the heavier its cargo
+    pub fn tow_price(&self, depth: i32, cargo_mass: f32) -> u32 {
+        self.tow_base_cost
+            + (depth.max(0) as f32 * self.tow_cost_per_depth + cargo_mass * self.tow_cost_per_kg)
+                .round() as u32
+    }
+
+    pub fn consumable_cost(&self, consumable: Consumable) -> u32 {
+        match consumable {
+            Consumable::RepairKit => self.repair_kit_cost,
+            Consumable::FuelCanister => self.fuel_canister_cost,
+            Consumable::Teleporter => self.teleporter_cost,
+            Consumable::Shield => self.shield_cost,
+            Consumable::Overclock => self.overclock_cost,
+        }
+    }
 }
 
 impl Default for EconomyConfig {
@@ -44,14 +70,47 @@ impl Default for EconomyConfig {
         EconomyConfig {
             fuel_price_per_unit: 2,
             fuel_refill_amount: 100.0,
-            upgrade_drill_cost: 80,
-            upgrade_speed_cost: 60,
-            upgrade_tank_cost: 100,
-            upgrade_armor_cost: 70,
+            dynamite_cost: 20,
+            repair_kit_cost: 40,
+            fuel_canister_cost: 30,
+            teleporter_cost: 60,
+            shield_cost: 50,
+            overclock_cost: 45,
+            recall_device_cost: 150,
+            repair_cost_per_point: 2,
+            tow_base_cost: 30,
+            tow_cost_per_depth: 0.5,
+            tow_cost_per_kg: 0.25,
+        }
+    }
+}
+
+/// Local co-op settings, picked in the main menu before the run starts
+#[derive(Resource)]
+pub struct CoopConfig {
+    pub players: usize,
+    // With a shared wallet every purchase and sale goes through the first player's currency
+    pub shared_wallet: bool,
+}
+
+impl Default for CoopConfig {
+    fn default() -> Self {
+        Self {
+            players: 1,
+            shared_wallet: true,
         }
     }
 }
 
+impl CoopConfig {
+    pub const MAX_PLAYERS: usize = 2;
+
+    /// Player whose currency pays for what the given player buys
+    pub fn wallet_of(&self, player: usize) -> usize {
+        if self.shared_wallet { 0 } else { player }
+    }
+}
+
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
@@ -80,16 +139,24 @@ impl Plugin for GamePlugin {
                     .chain(),
             )
             .insert_resource(EconomyConfig::default())
+            .init_resource::<CoopConfig>()
             .init_state::<GameState>()
             .init_state::<MenuState>()
+            .add_plugins(ControlsPlugin)
             .add_plugins(ResourcePlugin)
             .add_plugins(MenuPlugin)
             .add_plugins(MapPlugin)
+            .add_plugins(MarketPlugin)
             .add_plugins(CameraPlugin)
             .add_plugins(WorldBasePlugin)
+            .add_plugins(WreckPlugin)
+            .add_plugins(StatsPlugin)
+            .add_plugins(AchievementsPlugin)
             .add_plugins(PlayerPlugin)
             .add_plugins(HUDPlugin)
+            .add_plugins(MinimapPlugin)
             .add_plugins(GameAnimationPlugin)
+            .add_plugins(NetworkPlugin)
             .add_systems(OnEnter(GameState::GameOver), exit_game);
         /*
                #[cfg(debug_assertions)]
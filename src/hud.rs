@@ -2,19 +2,24 @@ use crate::map::TILE_SIZE;
 use crate::player::Player;
 use crate::prelude::GameState::Rendering;
 use crate::prelude::GameSystems::Ui;
-use crate::prelude::{Currency, Fuel, GameAssets, Health, Inventory, world_to_grid_position};
+use crate::prelude::{
+    AchievementUnlockedEvent, Action, HostNoticeEvent, MarketNewsEvent, CoopConfig, Currency, DrillBlockedEvent, PlayerBindings, PlayerId, Salvage, DrillHeat, Fuel, GameAssets, Health, Hotbar, Inventory, RecallDevice, StatusEffects, StatusKind, Tool,
+    ToolBelt, world_to_grid_position,
+};
 use bevy::prelude::{
-    App, AssetServer, Color, Commands, Component, Entity, FlexDirection, ImageNode,
+    AlignItems, App, AssetServer, Children, Color, Commands, Display, Component, Entity, EventReader, FlexDirection, ImageNode,
     IntoScheduleConfigs, JustifyContent, Node, OnEnter, Plugin, PositionType, Query, Res, Text,
-    TextColor, TextFont, TextLayout, TextUiWriter, TextureAtlas, Transform, Update, Val, With,
+    TextColor, TextFont, TextLayout, TextUiWriter, TextureAtlas, Time, Timer, TimerMode,
+    Transform, Update, Val, Visibility, With,
 };
-use bevy::text::JustifyText::{Left, Right};
+use bevy::text::JustifyText::{Center, Left, Right};
 use bevy::text::TextSpan;
 use bevy::ui::AlignItems::Start;
 use bevy::ui::Val::Px;
 use bevy::ui::widget::NodeImageMode;
 use bevy::ui::{BackgroundColor, UiRect};
 use bevy::utils::default;
+use std::collections::VecDeque;
 use std::ops::Mul;
 
 pub struct HUDPlugin;
@@ -22,6 +27,10 @@ pub struct HUDPlugin;
 #[derive(Component)]
 struct Hud;
 
+/// Drill a HUD widget reports on, one set of widgets per player
+#[derive(Component, Clone, Copy)]
+struct HudOwner(PlayerId);
+
 #[derive(Component)]
 struct HudIntegrity;
 
@@ -39,10 +48,67 @@ struct HudCurrencyText;
 #[derive(Component)]
 struct HudFuelBar;
 
+#[derive(Component)]
+struct HudHeatBar;
+
+#[derive(Component)]
+struct HudToolText;
+
+#[derive(Component)]
+struct HudHotbarText;
+
+/// Charge-up and cooldown of the recall device, hidden until it is bought
+#[derive(Component)]
+struct HudRecallText;
+
+/// Points at the wreck left by the previous run, hidden when there is none
+#[derive(Component)]
+struct HudWreckText;
+
+/// Icon of a status effect, hidden while the effect is not running
+#[derive(Component)]
+struct HudStatusIcon(StatusKind);
+
+/// Short-lived message shown at the bottom of the screen
+#[derive(Component)]
+pub struct HudHint {
+    pub timer: Timer,
+}
+
+impl HudHint {
+    pub const DURATION_SECS: f32 = 2.0;
+}
+
+/// Banner announcing unlocked achievements and market news one after the other
+#[derive(Component)]
+struct HudToast {
+    queue: VecDeque<String>,
+    timer: Timer,
+}
+
+impl HudToast {
+    const DURATION_SECS: f32 = 4.0;
+}
+
 impl Plugin for HUDPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(Rendering), init_hud)
-            .add_systems(Update, update_hud.in_set(Ui));
+            .add_systems(
+                Update,
+                (
+                    update_hud,
+                    update_heat_gauge,
+                    update_tool_text,
+                    update_recall_text,
+                    update_hotbar_text,
+                    update_status_icons,
+                    update_wreck_marker,
+                    show_drill_blocked_hint,
+                    fade_hud_hint,
+                    show_toasts,
+                )
+                    .in_set(Ui),
+            );
     }
 }
 
@@ -50,7 +116,9 @@ fn init_hud(
     mut commands: Commands,
     assets_server: Res<AssetServer>,
     game_assets: Res<GameAssets>,
+    coop: Res<CoopConfig>,
     hud_query: Query<Entity, With<Hud>>,
+    hint_query: Query<Entity, With<HudHint>>,
 ) {
     let font = assets_server.load("fonts/FiraSans-Regular.ttf");
 
@@ -60,127 +128,313 @@ fn init_hud(
         ..Default::default()
     };
     //Clear previous hud statistics, if any
-    for entity in hud_query.iter() {
+    for entity in hud_query.iter().chain(hint_query.iter()) {
         commands.entity(entity).despawn();
     }
-    commands
-        .spawn((
-            Hud,
-            BackgroundColor(Color::NONE),
-            Node {
-                width: Val::Percent(100.0),
-                height: Val::Percent(100.0),
-                align_items: Start,
-                flex_direction: FlexDirection::Row,
-                position_type: PositionType::Absolute,
-                left: Px(10.0),
-                top: Px(10.0),
-                padding: UiRect::all(Px(10.0)),
-                justify_content: JustifyContent::FlexStart,
-                ..default()
-            },
-        ))
-        .with_children(|hud_children| {
-            // Currency
-            hud_children
-                .spawn((
-                    Text::new("Money: "),
-                    font_style.clone(),
-                    TextColor(Color::srgb(255.0, 215.0, 0.0)),
-                    TextLayout::new_with_justify(Right),
-                    HudCurrencyText,
-                ))
-                .with_child((TextSpan::default(), font_style.clone()));
-            // Integrity stat
-            hud_children.spawn((
-                ImageNode::from_atlas_image(
-                    game_assets.hud[0].texture.clone(),
-                    TextureAtlas {
-                        layout: game_assets.hud[0].texture_layout.clone(),
-                        index: 0,
-                    },
-                )
-                .with_mode(NodeImageMode::Auto),
+    commands.spawn((
+        Text::default(),
+        font_style.clone(),
+        TextColor(Color::srgb(1.0, 0.8, 0.3)),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Px(40.0),
+            width: Val::Percent(100.0),
+            ..default()
+        },
+        TextLayout::new_with_justify(Center),
+        Visibility::Hidden,
+        HudHint {
+            timer: Timer::from_seconds(HudHint::DURATION_SECS, TimerMode::Once),
+        },
+    ));
+    // Achievement toast
+    let mut toast_timer = Timer::from_seconds(HudToast::DURATION_SECS, TimerMode::Once);
+    toast_timer.tick(toast_timer.duration());
+    commands.spawn((
+        Hud,
+        Text::default(),
+        font_style.clone(),
+        TextColor(Color::srgb(1.0, 0.85, 0.2)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Px(60.0),
+            width: Val::Percent(100.0),
+            ..default()
+        },
+        TextLayout::new_with_justify(Center),
+        Visibility::Hidden,
+        HudToast {
+            queue: VecDeque::new(),
+            timer: toast_timer,
+        },
+    ));
+    // Wreck marker
+    commands.spawn((
+        Hud,
+        Text::default(),
+        font_style.clone(),
+        TextColor(Color::srgb(0.95, 0.65, 0.2)),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Px(20.0),
+            bottom: Px(10.0),
+            display: Display::None,
+            ..default()
+        },
+        HudWreckText,
+    ));
+    for index in 0..coop.players {
+        let owner = HudOwner(PlayerId(index));
+        // The second player's widgets sit below the first row and on the right half
+        let row_top = 10.0 + index as f32 * 50.0;
+        let bottom_left = if index == 0 {
+            Px(20.0)
+        } else {
+            Val::Percent(50.0)
+        };
+        // Status effect icons
+        commands
+            .spawn((
+                Hud,
+                owner,
                 Node {
-                    width: Val::Px(TILE_SIZE.mul(1.5)),
-                    height: Val::Px(TILE_SIZE.mul(2.0)),
+                    position_type: PositionType::Absolute,
+                    left: bottom_left,
+                    bottom: Px(40.0),
+                    column_gap: Px(6.0),
                     ..default()
                 },
-                HudIntegrity,
+            ))
+            .with_children(|icons| {
+                for kind in StatusKind::ALL {
+                    icons
+                        .spawn((
+                            Node {
+                                width: Px(28.0),
+                                height: Px(28.0),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                display: Display::None,
+                                ..default()
+                            },
+                            BackgroundColor(status_color(kind)),
+                            HudStatusIcon(kind),
+                            owner,
+                        ))
+                        .with_child((
+                            Text::default(),
+                            TextFont {
+                                font: font.clone(),
+                                font_size: 14.0,
+                                ..Default::default()
+                            },
+                            TextColor(Color::BLACK),
+                        ));
+                }
+            });
+        // Hotbar
+        commands
+            .spawn((
+                Hud,
+                owner,
+                Text::default(),
+                font_style.clone(),
+                TextColor(Color::WHITE),
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: bottom_left,
+                    bottom: Px(10.0),
+                    ..default()
+                },
+                HudHotbarText,
             ));
-            // Fuel bar
-            hud_children
-                .spawn((
-                    Node {
-                        width: Val::Px(100.0),
-                        height: Val::Px(16.0),
-                        margin: UiRect::left(Val::Px(10.0)),
-                        ..default()
-                    },
-                    BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
-                ))
-                .with_child((
+        commands
+            .spawn((
+                Hud,
+                owner,
+                BackgroundColor(Color::NONE),
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: Start,
+                    flex_direction: FlexDirection::Row,
+                    position_type: PositionType::Absolute,
+                    left: Px(10.0),
+                    top: Px(row_top),
+                    padding: UiRect::all(Px(10.0)),
+                    justify_content: JustifyContent::FlexStart,
+                    ..default()
+                },
+            ))
+            .with_children(|hud_children| {
+                // Whose row this is, only needed with more than one drill
+                if coop.players > 1 {
+                    hud_children.spawn((
+                        Text::new(format!("P{} ", index + 1)),
+                        font_style.clone(),
+                        TextColor(owner.0.color()),
+                    ));
+                }
+                // Currency
+                hud_children
+                    .spawn((
+                        Text::new("Money: "),
+                        font_style.clone(),
+                        TextColor(Color::srgb(255.0, 215.0, 0.0)),
+                        TextLayout::new_with_justify(Right),
+                        HudCurrencyText,
+                        owner,
+                    ))
+                    .with_child((TextSpan::default(), font_style.clone()));
+                // Integrity stat
+                hud_children.spawn((
+                    ImageNode::from_atlas_image(
+                        game_assets.hud[0].texture.clone(),
+                        TextureAtlas {
+                            layout: game_assets.hud[0].texture_layout.clone(),
+                            index: 0,
+                        },
+                    )
+                    .with_mode(NodeImageMode::Auto),
                     Node {
-                        width: Val::Percent(100.0),
-                        height: Val::Percent(100.0),
+                        width: Val::Px(TILE_SIZE.mul(1.5)),
+                        height: Val::Px(TILE_SIZE.mul(2.0)),
                         ..default()
                     },
-                    BackgroundColor(Color::srgb(1.0, 0.6, 0.0)),
-                    HudFuelBar,
+                    HudIntegrity,
+                    owner,
                 ));
+                // Fuel bar
+                hud_children
+                    .spawn((
+                        Node {
+                            width: Val::Px(100.0),
+                            height: Val::Px(16.0),
+                            margin: UiRect::left(Val::Px(10.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                    ))
+                    .with_child((
+                        Node {
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgb(1.0, 0.6, 0.0)),
+                        HudFuelBar,
+                        owner,
+                    ));
+                // Drill heat gauge
+                hud_children
+                    .spawn((
+                        Node {
+                            width: Val::Px(100.0),
+                            height: Val::Px(16.0),
+                            margin: UiRect::left(Val::Px(10.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                    ))
+                    .with_child((
+                        Node {
+                            width: Val::Percent(0.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgb(0.9, 0.8, 0.2)),
+                        HudHeatBar,
+                        owner,
+                    ));
 
-            // Depth stat
-            hud_children
-                .spawn((
-                    Text::new("Depth: "),
-                    font_style.clone(),
-                    TextColor(Color::WHITE),
-                    TextLayout::new_with_justify(Left),
-                    HudDepthText,
-                ))
-                .with_child((TextSpan::default(), font_style.clone()));
-            // Velocity stat
-            hud_children
-                .spawn((
-                    Text::new("Fuel: "),
-                    font_style.clone(),
-                    TextColor(Color::WHITE),
-                    TextLayout::new_with_justify(Left),
-                    HudFuelText,
-                ))
-                .with_child((TextSpan::default(), font_style.clone()));
-            // Inventory stat
-            hud_children
-                .spawn((
-                    Text::new("Inventory: "),
-                    font_style.clone(),
-                    TextColor(Color::WHITE),
-                    TextLayout::new_with_justify(Left),
-                    HudInventoryText,
-                ))
-                .with_child((TextSpan::default(), font_style.clone()));
-        });
+                // Depth stat
+                hud_children
+                    .spawn((
+                        Text::new("Depth: "),
+                        font_style.clone(),
+                        TextColor(Color::WHITE),
+                        TextLayout::new_with_justify(Left),
+                        HudDepthText,
+                        owner,
+                    ))
+                    .with_child((TextSpan::default(), font_style.clone()));
+                // Velocity stat
+                hud_children
+                    .spawn((
+                        Text::new("Fuel: "),
+                        font_style.clone(),
+                        TextColor(Color::WHITE),
+                        TextLayout::new_with_justify(Left),
+                        HudFuelText,
+                        owner,
+                    ))
+                    .with_child((TextSpan::default(), font_style.clone()));
+                // Inventory stat
+                hud_children
+                    .spawn((
+                        Text::new("Inventory: "),
+                        font_style.clone(),
+                        TextColor(Color::WHITE),
+                        TextLayout::new_with_justify(Left),
+                        HudInventoryText,
+                        owner,
+                    ))
+                    .with_child((TextSpan::default(), font_style.clone()));
+                // Selected tool
+                hud_children
+                    .spawn((
+                        Text::new("Tool: "),
+                        font_style.clone(),
+                        TextColor(Color::WHITE),
+                        TextLayout::new_with_justify(Left),
+                        HudToolText,
+                        owner,
+                    ))
+                    .with_child((TextSpan::default(), font_style.clone()));
+                // Recall device
+                hud_children
+                    .spawn((
+                        Text::new("Recall: "),
+                        font_style.clone(),
+                        TextColor(Color::WHITE),
+                        TextLayout::new_with_justify(Left),
+                        Node {
+                            display: Display::None,
+                            ..default()
+                        },
+                        HudRecallText,
+                        owner,
+                    ))
+                    .with_child((TextSpan::default(), font_style.clone()));
+            });
+    }
 }
 
 fn update_hud(
-    mut hud_integrity: Query<(Entity, &mut ImageNode), With<HudIntegrity>>,
-    mut hud_fuel_bar: Query<&mut Node, With<HudFuelBar>>,
-    hud_depth_text: Query<Entity, With<HudDepthText>>,
-    hud_fuel_text: Query<Entity, With<HudFuelText>>,
-    hud_inventory_text: Query<Entity, With<HudInventoryText>>,
-    hud_currency_text: Query<Entity, With<HudCurrencyText>>,
-    player: Query<(&Health, &Transform, &Fuel, &Inventory, &Currency), With<Player>>,
+    mut hud_integrity: Query<(&HudOwner, &mut ImageNode), With<HudIntegrity>>,
+    mut hud_fuel_bar: Query<(&HudOwner, &mut Node), With<HudFuelBar>>,
+    hud_depth_text: Query<(&HudOwner, Entity), With<HudDepthText>>,
+    hud_fuel_text: Query<(&HudOwner, Entity), With<HudFuelText>>,
+    hud_inventory_text: Query<(&HudOwner, Entity), With<HudInventoryText>>,
+    hud_currency_text: Query<(&HudOwner, Entity), With<HudCurrencyText>>,
+    player: Query<(&PlayerId, &Health, &Transform, &Fuel, &Inventory), With<Player>>,
+    wallets: Query<(&PlayerId, &Currency), With<Player>>,
+    coop: Res<CoopConfig>,
     mut text_writer: TextUiWriter,
 ) {
-    // Updating hud stats
-    if let Ok(player_stats) = player.single() {
-        if let Ok(currency_text_entity) = hud_currency_text.single() {
-            let currency_amount = player_stats.4.amount;
-            *text_writer.text(currency_text_entity, 1) = format!("{}", currency_amount);
+    // Updating hud stats, each row from its own drill
+    for (player_id, health, transform, fuel, inventory) in player.iter() {
+        let owned_by = |owner: &HudOwner| owner.0 == *player_id;
+        let wallet = coop.wallet_of(player_id.0);
+        if let (Some((_, currency_text_entity)), Some((_, currency))) = (
+            hud_currency_text.iter().find(|(owner, _)| owned_by(owner)),
+            wallets.iter().find(|(id, _)| id.0 == wallet),
+        ) {
+            *text_writer.text(currency_text_entity, 1) = format!("{}", currency.amount);
         }
-        if let Ok((_, mut image_node)) = hud_integrity.single_mut() {
-            let health = player_stats.0;
-
+        if let Some((_, mut image_node)) =
+            hud_integrity.iter_mut().find(|(owner, _)| owned_by(owner))
+        {
             // texture index on the base of health level
             let health_ratio = (health.current / health.max).clamp(0.0, 1.0);
             let health_level_index = ((1.0 - health_ratio) * 10.0).round() as usize;
@@ -188,22 +442,257 @@ fn update_hud(
                 texture_atlas.index = health_level_index;
             };
         }
-        if let Ok(mut fuel_bar_node) = hud_fuel_bar.single_mut() {
-            let fuel = player_stats.2;
+        if let Some((_, mut fuel_bar_node)) =
+            hud_fuel_bar.iter_mut().find(|(owner, _)| owned_by(owner))
+        {
             let fuel_percentage = (fuel.current / fuel.max * 100.0).clamp(0.0, 100.0);
             fuel_bar_node.width = Val::Percent(fuel_percentage);
         }
-        if let Ok(depth_text_entity) = hud_depth_text.single() {
-            let position = world_to_grid_position(player_stats.1.translation.truncate());
+        if let Some((_, depth_text_entity)) =
+            hud_depth_text.iter().find(|(owner, _)| owned_by(owner))
+        {
+            let position = world_to_grid_position(transform.translation.truncate());
             *text_writer.text(depth_text_entity, 1) = format!("{}", position.1);
         }
-        if let Ok(fuel_text_entity) = hud_fuel_text.single() {
-            let fuel = player_stats.2;
+        if let Some((_, fuel_text_entity)) =
+            hud_fuel_text.iter().find(|(owner, _)| owned_by(owner))
+        {
             *text_writer.text(fuel_text_entity, 1) = format!("{}", fuel.current.trunc());
         }
-        if let Ok(inventory_text_entity) = hud_inventory_text.single() {
-            let inventory = player_stats.3;
-            *text_writer.text(inventory_text_entity, 1) = format!("{:?}", inventory.print_items());
+        if let Some((_, inventory_text_entity)) =
+            hud_inventory_text.iter().find(|(owner, _)| owned_by(owner))
+        {
+            *text_writer.text(inventory_text_entity, 1) = format!(
+                "{:?} ({:.0} kg)",
+                inventory.print_items(),
+                inventory.total_mass()
+            );
+        }
+    }
+}
+
+fn update_heat_gauge(
+    mut hud_heat_bar: Query<(&HudOwner, &mut Node, &mut BackgroundColor), With<HudHeatBar>>,
+    player: Query<(&PlayerId, &DrillHeat), With<Player>>,
+) {
+    for (owner, mut heat_bar_node, mut heat_bar_color) in hud_heat_bar.iter_mut() {
+        let Some((_, heat)) = player.iter().find(|(player_id, _)| **player_id == owner.0) else {
+            continue;
+        };
+        let heat_percentage = (heat.current / heat.max * 100.0).clamp(0.0, 100.0);
+        heat_bar_node.width = Val::Percent(heat_percentage);
+        heat_bar_color.0 = if heat.stalled || heat.current > heat.overheat_threshold {
+            Color::srgb(1.0, 0.1, 0.1)
+        } else {
+            Color::srgb(0.9, 0.8, 0.2)
+        };
+    }
+}
+
+fn update_tool_text(
+    hud_tool_text: Query<(&HudOwner, Entity), With<HudToolText>>,
+    player: Query<(&PlayerId, &ToolBelt), With<Player>>,
+    mut text_writer: TextUiWriter,
+) {
+    for (owner, tool_text_entity) in hud_tool_text.iter() {
+        let Some((_, tool_belt)) = player.iter().find(|(player_id, _)| **player_id == owner.0)
+        else {
+            continue;
+        };
+        *text_writer.text(tool_text_entity, 1) = match tool_belt.selected {
+            Tool::Dynamite => format!("{} x{}", tool_belt.selected.name(), tool_belt.dynamite),
+            tool => tool.name().to_string(),
+        };
+    }
+}
+
+fn update_recall_text(
+    mut hud_recall_text: Query<(&HudOwner, Entity, &mut Node), With<HudRecallText>>,
+    player: Query<(&PlayerId, &RecallDevice), With<Player>>,
+    mut text_writer: TextUiWriter,
+) {
+    for (owner, recall_text_entity, mut node) in hud_recall_text.iter_mut() {
+        let Some((_, device)) = player.iter().find(|(player_id, _)| **player_id == owner.0) else {
+            continue;
+        };
+        if !device.owned {
+            node.display = Display::None;
+            continue;
+        }
+        node.display = Display::Flex;
+        *text_writer.text(recall_text_entity, 1) = match &device.channel {
+            Some(channel) => format!("charging {:.1}s", channel.remaining_secs()),
+            None if !device.cooldown.finished() => {
+                format!("recharging {:.0}s", device.cooldown.remaining_secs())
+            }
+            None => "ready".to_string(),
+        };
+    }
+}
+
+fn update_hotbar_text(
+    mut hud_hotbar_text: Query<(&HudOwner, &mut Text), With<HudHotbarText>>,
+    player: Query<(&PlayerId, &Hotbar, &Inventory), With<Player>>,
+    player_bindings: Res<PlayerBindings>,
+) {
+    for (owner, mut text) in hud_hotbar_text.iter_mut() {
+        let Some((_, hotbar, inventory)) =
+            player.iter().find(|(player_id, ..)| **player_id == owner.0)
+        else {
+            continue;
+        };
+        let key_bindings = player_bindings.get(owner.0.0);
+        text.0 = hotbar
+            .slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                slot.map(|consumable| {
+                    format!(
+                        "[{}] {} x{}",
+                        key_bindings.describe(Action::UseItem(index)),
+                        consumable.name(),
+                        inventory.count(consumable.id())
+                    )
+                })
+            })
+            .collect::<Vec<_>>()
+            .join("   ");
+    }
+}
+
+fn update_status_icons(
+    mut hud_status_icons: Query<(&HudOwner, &HudStatusIcon, &mut Node, &Children)>,
+    mut texts: Query<&mut Text>,
+    player: Query<(&PlayerId, &StatusEffects), With<Player>>,
+) {
+    for (owner, icon, mut node, children) in hud_status_icons.iter_mut() {
+        let Some((_, status_effects)) =
+            player.iter().find(|(player_id, _)| **player_id == owner.0)
+        else {
+            continue;
+        };
+        let Some(effect) = status_effects.get(icon.0) else {
+            node.display = Display::None;
+            continue;
+        };
+        node.display = Display::Flex;
+        // First letter of the effect, with the stacks when there is more than one
+        let letter = &icon.0.name()[..1];
+        let label = if effect.stacks > 1 {
+            format!("{}{}", letter, effect.stacks)
+        } else {
+            letter.to_string()
+        };
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.0 = label.clone();
+            }
+        }
+    }
+}
+
+fn update_wreck_marker(
+    mut hud_wreck_text: Query<(&mut Text, &mut Node), With<HudWreckText>>,
+    player: Query<&Transform, With<Player>>,
+    salvage: Query<&Transform, With<Salvage>>,
+) {
+    let Ok((mut text, mut node)) = hud_wreck_text.single_mut() else {
+        return;
+    };
+    let Ok(salvage_transform) = salvage.single() else {
+        node.display = Display::None;
+        return;
+    };
+    // In co-op the marker guides whichever drill is closest to the wreck
+    let salvage_position = salvage_transform.translation.truncate();
+    let Some(player_position) = player
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .min_by(|a, b| {
+            a.distance_squared(salvage_position)
+                .total_cmp(&b.distance_squared(salvage_position))
+        })
+    else {
+        node.display = Display::None;
+        return;
+    };
+    node.display = Display::Flex;
+    let offset = (salvage_position - player_position) / TILE_SIZE;
+    // The HUD font only has the four straight arrows, so both legs of the way are shown
+    let horizontal = if offset.x < 0.0 { '←' } else { '→' };
+    let vertical = if offset.y < 0.0 { '↓' } else { '↑' };
+    text.0 = format!(
+        "Wreck {} {:.0}  {} {:.0}",
+        horizontal,
+        offset.x.abs(),
+        vertical,
+        offset.y.abs()
+    );
+}
+
+fn status_color(kind: StatusKind) -> Color {
+    match kind {
+        StatusKind::Burning => Color::srgb(1.0, 0.4, 0.1),
+        StatusKind::Slowed => Color::srgb(0.5, 0.5, 0.9),
+        StatusKind::LeakingFuel => Color::srgb(0.9, 0.7, 0.2),
+        StatusKind::Shielded => Color::srgb(0.3, 0.9, 1.0),
+        StatusKind::Overclocked => Color::srgb(0.8, 0.3, 0.9),
+    }
+}
+
+fn show_drill_blocked_hint(
+    mut events: EventReader<DrillBlockedEvent>,
+    mut hud_hint: Query<(&mut Text, &mut Visibility, &mut HudHint)>,
+) {
+    if let Some(event) = events.read().last() {
+        if let Ok((mut text, mut visibility, mut hint)) = hud_hint.single_mut() {
+            text.0 = format!("Too hard: drill head tier {} required", event.required_tier);
+            *visibility = Visibility::Visible;
+            hint.timer.reset();
+        }
+    }
+}
+
+fn show_toasts(
+    time: Res<Time>,
+    mut events: EventReader<AchievementUnlockedEvent>,
+    mut news_events: EventReader<MarketNewsEvent>,
+    mut notice_events: EventReader<HostNoticeEvent>,
+    mut hud_toast: Query<(&mut Text, &mut Visibility, &mut HudToast)>,
+) {
+    let Ok((mut text, mut visibility, mut toast)) = hud_toast.single_mut() else {
+        return;
+    };
+    for event in events.read() {
+        toast.queue.push_back(format!(
+            "Achievement unlocked: {}\n{}",
+            event.name, event.description
+        ));
+    }
+    for event in news_events.read() {
+        toast.queue.push_back(event.headline.clone());
+    }
+    for event in notice_events.read() {
+        toast.queue.push_back(event.text.clone());
+    }
+    if !toast.timer.tick(time.delta()).finished() {
+        return;
+    }
+    match toast.queue.pop_front() {
+        Some(message) => {
+            text.0 = message;
+            *visibility = Visibility::Visible;
+            toast.timer.reset();
+        }
+        None => *visibility = Visibility::Hidden,
+    }
+}
+
+fn fade_hud_hint(time: Res<Time>, mut hud_hint: Query<(&mut Visibility, &mut HudHint)>) {
+    if let Ok((mut visibility, mut hint)) = hud_hint.single_mut() {
+        if hint.timer.tick(time.delta()).just_finished() {
+            *visibility = Visibility::Hidden;
         }
     }
 }
@@ -1,23 +1,37 @@
+mod achievements;
 mod animation;
 mod camera;
+mod controls;
 mod game;
 mod hud;
 mod map;
+mod market;
 mod menu;
+mod minimap;
+mod network;
 mod player;
 mod resource;
+mod stats;
 mod world_base;
+mod wreck;
 
 mod prelude {
+    pub use crate::achievements::*;
     pub use crate::animation::*;
     pub use crate::camera::*;
+    pub use crate::controls::*;
     pub use crate::game::*;
     pub use crate::hud::*;
     pub use crate::map::*;
+    pub use crate::market::*;
     pub use crate::menu::*;
+    pub use crate::minimap::*;
+    pub use crate::network::*;
     pub use crate::player::*;
     pub use crate::resource::*;
+    pub use crate::stats::*;
     pub use crate::world_base::*;
+    pub use crate::wreck::*;
 }
 
 use crate::prelude::GamePlugin;
@@ -8,7 +8,7 @@ pub const GRID_HEIGHT: isize = 500;
 pub(super) const FILL_PROBABILITY: f32 = 0.55;
 pub(super) const SIMULATION_STEPS: usize = 4;
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum TileType {
     Solid,
     Sand,
@@ -20,6 +20,16 @@ pub enum TileType {
 }
 
 impl TileType {
+    /// Every kind of tile the drill can dig through
+    pub const SOLID: [TileType; 6] = [
+        TileType::Solid,
+        TileType::Sand,
+        TileType::Iron,
+        TileType::Copper,
+        TileType::Gold,
+        TileType::Crystal,
+    ];
+
     pub fn to_item(&self) -> Option<Item> {
         use TileType::*;
         match self {
@@ -29,27 +39,94 @@ impl TileType {
                 name: "Iron".to_string(),
                 quantity: 1,
                 value: 10,
+                mass: 8.0,
+                consumable: None,
             }),
             Copper => Some(Item {
                 id: "copper".to_string(),
                 name: "Copper".to_string(),
                 quantity: 1,
                 value: 5,
+                mass: 6.0,
+                consumable: None,
             }),
             Gold => Some(Item {
                 id: "gold".to_string(),
                 name: "Gold".to_string(),
                 quantity: 1,
                 value: 25,
+                mass: 12.0,
+                consumable: None,
             }),
             Crystal => Some(Item {
                 id: "crystal".to_string(),
                 name: "Crystal".to_string(),
                 quantity: 1,
                 value: 50,
+                mass: 4.0,
+                consumable: None,
             }),
         }
     }
+
+    pub fn is_ore(&self) -> bool {
+        use TileType::*;
+        matches!(self, Iron | Copper | Gold | Crystal)
+    }
+
+    pub fn texture_index(&self) -> usize {
+        use TileType::*;
+        match self {
+            Solid => 0,
+            Sand => 3,
+            Copper => 5,
+            Iron => 4,
+            Gold => 6,
+            Crystal => 7,
+            Empty => 0,
+        }
+    }
+
+    pub fn drilling(&self) -> Drilling {
+        use TileType::*;
+        match self {
+            Solid => Drilling {
+                integrity: 0.4,
+                hardness: 0.1,
+                min_tier: 1,
+            },
+            Sand => Drilling {
+                integrity: 0.1,
+                hardness: 0.05,
+                min_tier: 1,
+            },
+            Copper => Drilling {
+                integrity: 0.4,
+                hardness: 0.2,
+                min_tier: 1,
+            },
+            Iron => Drilling {
+                integrity: 0.6,
+                hardness: 0.3,
+                min_tier: 1,
+            },
+            Gold => Drilling {
+                integrity: 0.4,
+                hardness: 0.2,
+                min_tier: 2,
+            },
+            Crystal => Drilling {
+                integrity: 0.1,
+                hardness: 0.07,
+                min_tier: 3,
+            },
+            Empty => Drilling {
+                integrity: 0.0,
+                hardness: 0.0,
+                min_tier: 0,
+            },
+        }
+    }
 }
 
 #[derive(Component, Clone, Copy, PartialEq)]
@@ -62,6 +139,18 @@ pub struct Tile {
 pub struct Drilling {
     pub integrity: f32,
     pub hardness: f32,
+    // Lowest drill head tier able to break the tile
+    pub min_tier: u8,
+}
+
+/// Seed of the map generation, rolled for every run unless a wreck is waiting in the last world
+#[derive(Resource)]
+pub struct MapSeed(pub u64);
+
+impl Default for MapSeed {
+    fn default() -> Self {
+        Self(rand::random())
+    }
 }
 
 #[derive(Resource)]
@@ -72,6 +161,32 @@ pub struct WorldGrid {
     pub map_area: Rect,
 }
 
+impl WorldGrid {
+    /// Turns the tile into empty space, returns its entity for the caller to despawn
+    pub fn clear_tile(&mut self, position: (i32, i32)) -> Option<Entity> {
+        let grid_id = world_grid_position_to_idx(position);
+        if let Some(tile) = self.tiles.get_mut(grid_id.1).and_then(|row| row.get_mut(grid_id.0)) {
+            *tile = TileType::Empty;
+        }
+        self.grid.remove(&position)
+    }
+
+    pub fn tile_type_at(&self, position: (i32, i32)) -> Option<TileType> {
+        if position.0 < -(GRID_WIDTH / 2) as i32
+            || position.0 >= (GRID_WIDTH / 2) as i32
+            || position.1 < -GRID_HEIGHT as i32
+            || position.1 >= 0
+        {
+            return None;
+        }
+        let (id_x, id_y) = world_grid_position_to_idx(position);
+        self.tiles
+            .get(id_y)
+            .and_then(|row| row.get(id_x))
+            .copied()
+    }
+}
+
 #[derive(Component, Clone, Copy, PartialEq)]
 #[allow(dead_code)]
 pub struct FovOverlay;
@@ -81,6 +196,8 @@ pub struct TileDestroyedEvent {
     pub tile_type: TileType,
     pub position: (i32, i32),
     pub entity: Entity,
+    // Drill that broke the tile and gets the loot
+    pub destroyed_by: Option<Entity>,
 }
 
 pub fn world_to_grid_position(world_position: Vec2) -> (i32, i32) {
@@ -94,6 +211,13 @@ pub fn world_to_grid_position(world_position: Vec2) -> (i32, i32) {
     )
 }
 
+pub fn grid_to_world_position(grid_position: (i32, i32)) -> Vec2 {
+    Vec2::new(
+        grid_position.0 as f32 * TILE_SIZE,
+        grid_position.1 as f32 * TILE_SIZE,
+    )
+}
+
 pub fn world_grid_position_to_idx(world_grid_position: (i32, i32)) -> (usize, usize) {
     (
         (world_grid_position.0 + (GRID_WIDTH / 2) as i32) as usize,
@@ -8,7 +8,8 @@ pub fn update_fov(
     mut player_query: Query<(&Transform, Mut<FieldOfView>), With<Player>>,
     world_grid: ResMut<WorldGrid>,
 ) {
-    if let Ok((player_transform, mut fov)) = player_query.single_mut() {
+    // Every drill lights up its own surroundings
+    for (player_transform, mut fov) in player_query.iter_mut() {
         let player_pos = IVec2::from(world_to_grid_position(
             player_transform.translation.truncate(),
         ));
@@ -62,7 +63,7 @@ pub fn update_fov_overlay(
     mut query_tiles: Query<(&mut Sprite, &Tile), With<Tile>>,
     mut world_grid: ResMut<WorldGrid>,
 ) {
-    if let Ok(mut fov) = fov_query.single_mut() {
+    for mut fov in fov_query.iter_mut() {
         if fov.dirty {
             fov.visible_tiles.iter().for_each(|(x, y)| {
                 if !world_grid.revealed_tiles.contains(&(*x, *y)) {
@@ -1,21 +1,25 @@
 use crate::map::components::{
-    Drilling, FILL_PROBABILITY, GRID_HEIGHT, GRID_WIDTH, SIMULATION_STEPS, TILE_SIZE,
-    Tile, TileDestroyedEvent, TileType, WorldGrid, world_grid_position_to_idx,
+    FILL_PROBABILITY, GRID_HEIGHT, GRID_WIDTH, SIMULATION_STEPS, TILE_SIZE,
+    MapSeed, Tile, TileDestroyedEvent, TileType, WorldGrid,
 };
 use crate::prelude::{GameAssets, LoadingProgress};
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::{ActiveEvents, Collider, RigidBody};
 use noise::{NoiseFn, Perlin};
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::collections::HashMap;
 use std::collections::HashSet;
 
 use TileType::*;
 
-pub fn initialize_world_grid(mut commands: Commands) {
-    info!("Generating map using Cellular Automata algorithm");
+pub fn initialize_world_grid(mut commands: Commands, map_seed: Res<MapSeed>) {
+    info!(
+        "Generating map using Cellular Automata algorithm, seed {}",
+        map_seed.0
+    );
     let mut tiles = vec![vec![Empty; GRID_WIDTH as usize]; GRID_HEIGHT as usize];
-    let mut rng = rand::thread_rng();
+    let mut rng = StdRng::seed_from_u64(map_seed.0);
 
     for y in 0..GRID_HEIGHT as usize {
         for x in 0..GRID_WIDTH as usize {
@@ -29,7 +33,7 @@ pub fn initialize_world_grid(mut commands: Commands) {
         tiles = simulation(&tiles, s);
     }
 
-    tiles = distribute_materials(&mut tiles);
+    tiles = distribute_materials(&mut tiles, rng.r#gen());
 
     commands.insert_resource(WorldGrid {
         grid: HashMap::new(),
@@ -45,8 +49,8 @@ pub fn initialize_world_grid(mut commands: Commands) {
     info!("Map generated");
 }
 
-fn distribute_materials(tiles: &mut Vec<Vec<TileType>>) -> Vec<Vec<TileType>> {
-    let perlin = Perlin::new(rand::thread_rng().gen());
+fn distribute_materials(tiles: &mut Vec<Vec<TileType>>, noise_seed: u32) -> Vec<Vec<TileType>> {
+    let perlin = Perlin::new(noise_seed);
     let mut materialized_tiles = tiles.clone();
 
     for y in 0..GRID_HEIGHT as usize {
@@ -188,78 +192,13 @@ pub fn render_map(
 }
 
 fn get_tile_to_render(tile_type: &TileType) -> (Tile, usize) {
-    match tile_type {
-        Solid => (
-            Tile {
-                tile_type: Solid,
-                drilling: Drilling {
-                    integrity: 0.4,
-                    hardness: 0.1,
-                },
-            },
-            0,
-        ),
-        Sand => (
-            Tile {
-                tile_type: Sand,
-                drilling: Drilling {
-                    integrity: 0.1,
-                    hardness: 0.05,
-                },
-            },
-            3,
-        ),
-        Copper => (
-            Tile {
-                tile_type: Copper,
-                drilling: Drilling {
-                    integrity: 0.4,
-                    hardness: 0.2,
-                },
-            },
-            5,
-        ),
-        Iron => (
-            Tile {
-                tile_type: Iron,
-                drilling: Drilling {
-                    integrity: 0.6,
-                    hardness: 0.3,
-                },
-            },
-            4,
-        ),
-        Gold => (
-            Tile {
-                tile_type: Gold,
-                drilling: Drilling {
-                    integrity: 0.4,
-                    hardness: 0.2,
-                },
-            },
-            6,
-        ),
-        Crystal => (
-            Tile {
-                tile_type: Crystal,
-                drilling: Drilling {
-                    integrity: 0.1,
-                    hardness: 0.07,
-                },
-            },
-            7,
-        ),
-        _ => (
-            Tile {
-                tile_type: Empty,
-                drilling: Drilling {
-                    integrity: 0.0,
-                    hardness: 0.0,
-                },
-            },
-            0,
-        ),
-    }
+    (
+        Tile {
+            tile_type: *tile_type,
+            drilling: tile_type.drilling(),
+        },
+        tile_type.texture_index(),
+    )
 }
 
 pub fn handle_tile_destroyed(
@@ -269,9 +208,7 @@ pub fn handle_tile_destroyed(
 ) {
     for event in events.read() {
         commands.entity(event.entity).despawn();
-        world_grid.grid.remove(&event.position);
-        let grid_id = world_grid_position_to_idx(event.position);
-        world_grid.tiles[grid_id.1][grid_id.0] = TileType::Empty;
+        world_grid.clear_tile(event.position);
     }
 }
 
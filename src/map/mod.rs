@@ -16,6 +16,7 @@ pub struct MapPlugin;
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<TileDestroyedEvent>()
+            .init_resource::<MapSeed>()
             .add_systems(
                 OnEnter(GameState::Rendering),
                 (initialize_world_grid, render_map, setup_borders)
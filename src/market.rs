@@ -0,0 +1,220 @@
+use crate::game::GameState;
+use crate::map::TileType;
+use crate::prelude::GameState::Playing;
+use crate::prelude::GameSystems::Running;
+use crate::prelude::simulates_world;
+use bevy::prelude::*;
+use rand::Rng;
+use std::collections::VecDeque;
+
+/// Every unit sold lowers the commodity's price by this fraction
+const SATURATION_PER_UNIT: f32 = 0.04;
+/// A flooded market still pays this fraction of the base price
+const MIN_SUPPLY_FACTOR: f32 = 0.3;
+/// Share of the missing demand that comes back every second
+const RECOVERY_RATE: f32 = 0.02;
+/// Seconds between two points of the price history
+const HISTORY_INTERVAL_SECS: f32 = 5.0;
+pub const HISTORY_LEN: usize = 24;
+/// How often a new market event may start while none is running, and how likely it is
+const EVENT_ROLL_SECS: f32 = 30.0;
+const EVENT_CHANCE: f64 = 0.35;
+
+/// Events the market can go through, each one changing the demand for one commodity
+const MARKET_EVENTS: [MarketEventKind; 4] = [
+    MarketEventKind {
+        name: "Gold rush",
+        description: "jewellers pay a premium for gold",
+        commodity: "gold",
+        multiplier: 1.8,
+        duration_secs: 90.0,
+    },
+    MarketEventKind {
+        name: "Copper shortage",
+        description: "copper is scarce, prices soar",
+        commodity: "copper",
+        multiplier: 2.2,
+        duration_secs: 90.0,
+    },
+    MarketEventKind {
+        name: "Iron glut",
+        description: "the foundries are full, iron sells cheap",
+        commodity: "iron",
+        multiplier: 0.6,
+        duration_secs: 60.0,
+    },
+    MarketEventKind {
+        name: "Crystal fever",
+        description: "collectors bid on every crystal",
+        commodity: "crystal",
+        multiplier: 1.5,
+        duration_secs: 60.0,
+    },
+];
+
+pub struct MarketPlugin;
+
+impl Plugin for MarketPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Market>()
+            .add_event::<MarketNewsEvent>()
+            .add_systems(OnEnter(GameState::Rendering), reset_market)
+            .add_systems(
+                Update,
+                update_market
+                    .in_set(Running)
+                    .run_if(in_state(Playing).and(simulates_world)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MarketEventKind {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub commodity: &'static str,
+    pub multiplier: f32,
+    pub duration_secs: f32,
+}
+
+/// Sent when a market event starts or ends, shown on the HUD
+#[derive(Event)]
+pub struct MarketNewsEvent {
+    pub headline: String,
+}
+
+/// Ore traded at the base, its price follows what the players sell
+#[derive(Clone, Debug)]
+pub struct Commodity {
+    pub id: String,
+    pub name: String,
+    pub base_price: u32,
+    // 1 when the demand is intact, lower the more the market has been flooded
+    supply_factor: f32,
+    // Oldest first, sampled every `HISTORY_INTERVAL_SECS`
+    pub history: VecDeque<u32>,
+}
+
+#[derive(Resource, Clone)]
+pub struct Market {
+    pub commodities: Vec<Commodity>,
+    pub event: Option<(MarketEventKind, Timer)>,
+    history_timer: Timer,
+    event_timer: Timer,
+}
+
+impl Default for Market {
+    fn default() -> Self {
+        // Base prices are the face values of the ores
+        let commodities = TileType::SOLID
+            .iter()
+            .filter_map(TileType::to_item)
+            .map(|item| Commodity {
+                id: item.id,
+                name: item.name,
+                base_price: item.value,
+                supply_factor: 1.0,
+                history: VecDeque::from([item.value]),
+            })
+            .collect();
+        Self {
+            commodities,
+            event: None,
+            history_timer: Timer::from_seconds(HISTORY_INTERVAL_SECS, TimerMode::Repeating),
+            event_timer: Timer::from_seconds(EVENT_ROLL_SECS, TimerMode::Repeating),
+        }
+    }
+}
+
+impl Market {
+    fn commodity(&self, id: &str) -> Option<&Commodity> {
+        self.commodities.iter().find(|commodity| commodity.id == id)
+    }
+
+    fn event_multiplier(&self, id: &str) -> f32 {
+        match &self.event {
+            Some((kind, _)) if kind.commodity == id => kind.multiplier,
+            _ => 1.0,
+        }
+    }
+
+    fn unit_price(&self, commodity: &Commodity) -> u32 {
+        let price = commodity.base_price as f32
+            * commodity.supply_factor
+            * self.event_multiplier(&commodity.id);
+        (price.round() as u32).max(1)
+    }
+
+    /// Current price of one unit, `None` for items the market does not trade
+    pub fn price(&self, id: &str) -> Option<u32> {
+        self.commodity(id).map(|commodity| self.unit_price(commodity))
+    }
+
+    /// Sells the units one at a time, each pushing the price down, and returns the earnings
+    pub fn sell(&mut self, id: &str, quantity: usize) -> Option<u32> {
+        let index = self.commodities.iter().position(|c| c.id == id)?;
+        let mut earned = 0;
+        for _ in 0..quantity {
+            earned += self.unit_price(&self.commodities[index]);
+            let commodity = &mut self.commodities[index];
+            commodity.supply_factor =
+                (commodity.supply_factor * (1.0 - SATURATION_PER_UNIT)).max(MIN_SUPPLY_FACTOR);
+        }
+        Some(earned)
+    }
+
+    /// What selling would pay right now, without moving the prices
+    pub fn quote(&self, id: &str, quantity: usize) -> Option<u32> {
+        self.clone().sell(id, quantity)
+    }
+}
+
+fn reset_market(mut market: ResMut<Market>) {
+    *market = Market::default();
+}
+
+/// Lets the demand recover, runs the market events and records the prices
+fn update_market(
+    time: Res<Time>,
+    mut market: ResMut<Market>,
+    mut news_events: EventWriter<MarketNewsEvent>,
+) {
+    let recovery = (RECOVERY_RATE * time.delta_secs()).min(1.0);
+    for commodity in market.commodities.iter_mut() {
+        commodity.supply_factor = commodity.supply_factor.lerp(1.0, recovery);
+    }
+
+    if let Some((kind, timer)) = &mut market.event {
+        if timer.tick(time.delta()).finished() {
+            info!("Market event over: {}", kind.name);
+            news_events.write(MarketNewsEvent {
+                headline: format!("Market: the {} is over", kind.name.to_lowercase()),
+            });
+            market.event = None;
+        }
+    } else if market.event_timer.tick(time.delta()).just_finished() {
+        let mut rng = rand::thread_rng();
+        if rng.gen_bool(EVENT_CHANCE) {
+            let kind = MARKET_EVENTS[rng.gen_range(0..MARKET_EVENTS.len())];
+            info!("Market event: {}", kind.name);
+            news_events.write(MarketNewsEvent {
+                headline: format!("Market: {}, {}", kind.name, kind.description),
+            });
+            market.event = Some((kind, Timer::from_seconds(kind.duration_secs, TimerMode::Once)));
+        }
+    }
+
+    if market.history_timer.tick(time.delta()).just_finished() {
+        let prices: Vec<u32> = market
+            .commodities
+            .iter()
+            .map(|commodity| market.unit_price(commodity))
+            .collect();
+        for (commodity, price) in market.commodities.iter_mut().zip(prices) {
+            commodity.history.push_back(price);
+            if commodity.history.len() > HISTORY_LEN {
+                commodity.history.pop_front();
+            }
+        }
+    }
+}
@@ -1,7 +1,11 @@
-use crate::prelude::MenuButton::{NewGame, QuitGame, Refill, Resume, Sell, UpgradeDrill, UpgradeSpeed, UpgradeTank, UpgradeArmor};
+#![allow(clippy::type_complexity)]
+
+use crate::prelude::MenuButton::{NewGame, QuitGame, Refill, Resume, Sell, BuyModule, SwapModule, BuyDynamite, BuyConsumable, BuyRecall, CallTow, ForfeitCargo, GiveUp, OpenSettings, OpenStats, OpenAchievements, Back, Rebind, ResetBindings, SwitchBindingsPlayer, TogglePlayers, ToggleWallet, OpenTrade, CloseTrade, TradeQuantity, SellOne, SellStack, SellSelected, Repair};
+use bevy_rapier2d::prelude::Velocity;
 use crate::prelude::*;
 use bevy::prelude::*;
 use bevy::ui::Interaction::Pressed;
+use std::collections::HashMap;
 
 pub struct MenuPlugin;
 
@@ -14,18 +18,44 @@ pub enum MenuState {
     Settings,
     Inventory,
     WorldBase,
+    Map,
+    Rescue,
+    Stats,
+    Achievements,
+    Trading,
 }
 #[derive(Component, Debug)]
 pub enum MenuButton {
     Sell,
     Refill,
+    Repair,
     Resume,
     NewGame,
     QuitGame,
-    UpgradeDrill,
-    UpgradeSpeed,
-    UpgradeTank,
-    UpgradeArmor,
+    CallTow,
+    ForfeitCargo,
+    GiveUp,
+    BuyModule(EquipmentSlot),
+    SwapModule(EquipmentSlot),
+    BuyDynamite,
+    BuyConsumable(Consumable),
+    BuyRecall,
+    OpenSettings,
+    OpenStats,
+    OpenAchievements,
+    Back,
+    Rebind(Action),
+    ResetBindings,
+    SwitchBindingsPlayer,
+    TogglePlayers,
+    ToggleWallet,
+    OpenTrade,
+    CloseTrade,
+    // Changes how many units of the stack are picked for "Sell selected"
+    TradeQuantity(String, i32),
+    SellOne(String),
+    SellStack(String),
+    SellSelected,
 }
 
 impl Plugin for MenuPlugin {
@@ -36,11 +66,83 @@ impl Plugin for MenuPlugin {
                 .in_set(GameSystems::Rendering)
                 .chain(),
         )
-        .add_systems(OnEnter(MenuState::WorldBase), handle_base_menu)
+        .init_resource::<PendingRebind>()
+        .init_resource::<MenuPlayer>()
+        .add_event::<LootSoldEvent>()
+        .init_resource::<MenuFocus>()
+        .init_resource::<TradeScreen>()
+        .add_systems(
+            OnEnter(MenuState::WorldBase),
+            (show_building_services, handle_base_menu).chain(),
+        )
+        .add_systems(OnExit(MenuState::WorldBase), cleanup_menu)
+        .add_systems(
+            OnTransition {
+                exited: MenuState::None,
+                entered: MenuState::WorldBase,
+            },
+            reset_trade_screen,
+        )
+        .add_systems(OnEnter(MenuState::Trading), handle_trade_menu)
+        .add_systems(OnExit(MenuState::Trading), cleanup_menu)
         .add_systems(OnEnter(MenuState::GameOver), handle_gameover_menu)
+        .add_systems(OnEnter(MenuState::Rescue), handle_rescue_menu)
+        .add_systems(OnExit(MenuState::Rescue), cleanup_menu)
         .add_systems(OnEnter(MenuState::Inventory), handle_inventory_menu)
         .add_systems(OnEnter(MenuState::Settings), handle_settings_menu)
-        .add_systems(Update, handle_button_interaction.in_set(GameSystems::Ui))
+        .add_systems(OnExit(MenuState::Settings), (cleanup_menu, cancel_rebind))
+        .add_systems(OnEnter(MenuState::Start), handle_start_menu)
+        .add_systems(OnEnter(MenuState::Stats), handle_stats_menu)
+        .add_systems(OnExit(MenuState::Stats), cleanup_menu)
+        .add_systems(OnEnter(MenuState::Achievements), handle_achievements_menu)
+        .add_systems(OnExit(MenuState::Achievements), cleanup_menu)
+        .add_systems(
+            Update,
+            pause_game
+                .in_set(GameSystems::Ui)
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            (capture_rebind, update_binding_buttons)
+                .chain()
+                .in_set(GameSystems::Ui)
+                .run_if(in_state(MenuState::Settings)),
+        )
+        .add_systems(
+            Update,
+            (
+                navigate_menu_with_gamepad.run_if(not(in_state(GameState::Playing))),
+                handle_button_interaction,
+            )
+                .chain()
+                .in_set(GameSystems::Ui),
+        )
+        .add_systems(
+            Update,
+            update_module_buttons
+                .in_set(GameSystems::Ui)
+                .run_if(in_state(MenuState::WorldBase)),
+        )
+        .add_systems(
+            Update,
+            update_trade_list
+                .after(handle_button_interaction)
+                .in_set(GameSystems::Ui)
+                .run_if(in_state(MenuState::Trading)),
+        )
+        .add_systems(
+            Update,
+            update_price_chart
+                .in_set(GameSystems::Ui)
+                .run_if(in_state(MenuState::WorldBase)),
+        )
+        .add_systems(
+            Update,
+            update_coop_buttons
+                .in_set(GameSystems::Ui)
+                .run_if(in_state(MenuState::Start)),
+        )
         .add_systems(OnExit(GameState::Menu), cleanup_menu)
         .add_systems(OnExit(GameState::MainMenu), cleanup_menu);
     }
@@ -49,7 +151,123 @@ impl Plugin for MenuPlugin {
 #[derive(Component)]
 pub struct Menu;
 
-pub fn init_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
+#[derive(Component)]
+pub struct DamageLogText;
+
+#[derive(Component)]
+pub struct RescueText;
+
+#[derive(Component)]
+pub struct BaseTitleText;
+
+/// Part of the base menu shown only in the buildings offering the service
+#[derive(Component)]
+pub struct ServiceSection(BuildingService);
+
+/// Sent when cargo is sold at the base, with everything sold since the drill arrived
+#[derive(Event)]
+pub struct LootSoldEvent {
+    pub items: Vec<Item>,
+}
+
+#[derive(Component)]
+pub struct RunStatsText;
+
+#[derive(Component)]
+pub struct MarketNewsText;
+
+/// Container of the trading screen rows, rebuilt whenever the cargo or the prices change
+#[derive(Component)]
+pub struct TradeList;
+
+#[derive(Component)]
+pub struct TradeSummaryText;
+
+/// Row and column of a trading screen button, to keep the gamepad focus across rebuilds
+#[derive(Component)]
+pub struct TradeCell(usize, usize);
+
+/// Units of each cargo stack picked on the trading screen, and what this visit sold so far
+#[derive(Resource, Default)]
+pub struct TradeScreen {
+    selected: HashMap<String, usize>,
+    earned: u32,
+    sold: Vec<Item>,
+}
+
+impl TradeScreen {
+    /// Nothing by default, never more than the stack holds
+    fn selected_of(&self, item: &Item) -> usize {
+        self.selected
+            .get(&item.id)
+            .copied()
+            .unwrap_or(0)
+            .min(item.quantity)
+    }
+
+    /// Takes the picked units of every cargo stack out of the inventory
+    fn take_selected(&self, inventory: &mut Inventory) -> Vec<Item> {
+        let picked: Vec<(String, usize)> = inventory
+            .items
+            .iter()
+            .filter(|item| item.consumable.is_none())
+            .map(|item| (item.id.clone(), self.selected_of(item)))
+            .filter(|(_, quantity)| *quantity > 0)
+            .collect();
+        picked
+            .into_iter()
+            .filter_map(|(id, quantity)| inventory.take(&id, quantity))
+            .collect()
+    }
+}
+
+/// Current price of the commodity at this index of `Market::commodities`
+#[derive(Component)]
+pub struct PriceLabel(usize);
+
+/// One column of a commodity's price history chart, oldest sample first
+#[derive(Component)]
+pub struct PriceBar {
+    commodity: usize,
+    sample: usize,
+}
+
+/// Height (px) of a bar at twice the base price
+const PRICE_CHART_HEIGHT: f32 = 24.0;
+
+#[derive(Component)]
+pub struct LifetimeStatsText;
+
+#[derive(Component)]
+pub struct AchievementsText;
+
+/// Button highlighted for gamepad navigation, and the one confirmed this frame
+#[derive(Resource, Default)]
+pub struct MenuFocus {
+    button: Option<Entity>,
+    confirmed: Option<Entity>,
+}
+
+/// Action waiting for a key press in the settings menu, and whose bindings are being edited
+#[derive(Resource, Default)]
+pub struct PendingRebind {
+    action: Option<Action>,
+    player: usize,
+}
+
+/// Player the in-game menu was opened for: the one who reached the base or ran dry
+#[derive(Resource, Default)]
+pub struct MenuPlayer(pub PlayerId);
+
+/// Fuel left in the tank by the tow service, enough to reach the pump
+const TOW_FUEL: f32 = 10.0;
+
+pub fn init_menu(
+    mut commands: Commands,
+    assets_server: Res<AssetServer>,
+    economy: Res<EconomyConfig>,
+    market: Res<Market>,
+) {
     info!("Initializing menu");
     let font = assets_server.load("fonts/FiraSans-Regular.ttf");
 
@@ -122,6 +340,41 @@ pub fn init_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
                                     TextColor(Color::WHITE),
                                 ));
                             });
+                            popup.spawn((Button, TogglePlayers)).with_children(|button| {
+                                button.spawn((
+                                    Text::default(),
+                                    font_style.clone(),
+                                    TextColor(Color::WHITE),
+                                ));
+                            });
+                            popup.spawn((Button, ToggleWallet)).with_children(|button| {
+                                button.spawn((
+                                    Text::default(),
+                                    font_style.clone(),
+                                    TextColor(Color::WHITE),
+                                ));
+                            });
+                            popup.spawn((Button, OpenSettings)).with_children(|button| {
+                                button.spawn((
+                                    Text::new("Controls"),
+                                    font_style.clone(),
+                                    TextColor(Color::WHITE),
+                                ));
+                            });
+                            popup.spawn((Button, OpenStats)).with_children(|button| {
+                                button.spawn((
+                                    Text::new("Statistics"),
+                                    font_style.clone(),
+                                    TextColor(Color::WHITE),
+                                ));
+                            });
+                            popup.spawn((Button, OpenAchievements)).with_children(|button| {
+                                button.spawn((
+                                    Text::new("Achievements"),
+                                    font_style.clone(),
+                                    TextColor(Color::WHITE),
+                                ));
+                            });
                         });
                 });
             //World base menu [index-1]
@@ -136,58 +389,352 @@ pub fn init_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
                         Text::new("World base"),
                         font_style.clone(),
                         TextColor(Color::WHITE),
+                        BaseTitleText,
+                    ));
+                    popup
+                        .spawn((Button, Sell, ServiceSection(BuildingService::Exchange)))
+                        .with_children(|button| {
+                            button.spawn((
+                                Text::new("Sell inventory"),
+                                font_style.clone(),
+                                TextColor(Color::WHITE),
+                            ));
+                        });
+                    popup
+                        .spawn((Button, OpenTrade, ServiceSection(BuildingService::Exchange)))
+                        .with_children(|button| {
+                            button.spawn((
+                                Text::new("Trade cargo"),
+                                font_style.clone(),
+                                TextColor(Color::WHITE),
+                            ));
+                        });
+                    popup
+                        .spawn((Button, Refill, ServiceSection(BuildingService::Refuel)))
+                        .with_children(|button| {
+                            button.spawn((
+                                Text::new("Refill tank"),
+                                font_style.clone(),
+                                TextColor(Color::WHITE),
+                            ));
+                        });
+                    popup
+                        .spawn((Button, Repair, ServiceSection(BuildingService::Repair)))
+                        .with_children(|button| {
+                            button.spawn((
+                                Text::new(format!(
+                                    "Repair hull ({}c per point)",
+                                    economy.repair_cost_per_point
+                                )),
+                                font_style.clone(),
+                                TextColor(Color::WHITE),
+                            ));
+                        });
+                    popup
+                        .spawn((Button, BuyDynamite, ServiceSection(BuildingService::Shop)))
+                        .with_children(|button| {
+                            button.spawn((
+                                Text::new(format!(
+                                    "Buy Dynamite ({}c)",
+                                    economy.dynamite_cost
+                                )),
+                                font_style.clone(),
+                                TextColor(Color::WHITE),
+                            ));
+                        });
+                    for consumable in Consumable::ALL {
+                        popup
+                            .spawn((
+                                Button,
+                                BuyConsumable(consumable),
+                                ServiceSection(BuildingService::Shop),
+                            ))
+                            .with_children(|button| {
+                                button.spawn((
+                                    Text::new(format!(
+                                        "Buy {} ({}c)",
+                                        consumable.name(),
+                                        economy.consumable_cost(consumable)
+                                    )),
+                                    font_style.clone(),
+                                    TextColor(Color::WHITE),
+                                ));
+                            });
+                    }
+                    popup
+                        .spawn((Button, BuyRecall, ServiceSection(BuildingService::Shop)))
+                        .with_children(|button| {
+                            button.spawn((
+                                Text::new(format!(
+                                    "Buy Recall device ({}c)",
+                                    economy.recall_device_cost
+                                )),
+                                font_style.clone(),
+                                TextColor(Color::WHITE),
+                            ));
+                        });
+                    // Market prices: current price and recent history of every commodity
+                    popup.spawn((
+                        Text::default(),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 14.0,
+                            ..Default::default()
+                        },
+                        TextColor(Color::srgb(1.0, 0.8, 0.3)),
+                        MarketNewsText,
+                        ServiceSection(BuildingService::Exchange),
                     ));
-                    popup.spawn((Button, Sell)).with_children(|button| {
+                    for index in 0..market.commodities.len() {
+                        popup
+                            .spawn((
+                                Node {
+                                    flex_direction: FlexDirection::Row,
+                                    align_items: AlignItems::End,
+                                    column_gap: Val::Px(10.0),
+                                    ..default()
+                                },
+                                ServiceSection(BuildingService::Exchange),
+                            ))
+                            .with_children(|row| {
+                                row.spawn((
+                                    Text::default(),
+                                    TextFont {
+                                        font: font.clone(),
+                                        font_size: 14.0,
+                                        ..Default::default()
+                                    },
+                                    TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                                    Node {
+                                        width: Val::Px(140.0),
+                                        ..default()
+                                    },
+                                    PriceLabel(index),
+                                ));
+                                row.spawn(Node {
+                                    height: Val::Px(PRICE_CHART_HEIGHT),
+                                    align_items: AlignItems::End,
+                                    column_gap: Val::Px(1.0),
+                                    ..default()
+                                })
+                                .with_children(|chart| {
+                                    for sample in 0..HISTORY_LEN {
+                                        chart.spawn((
+                                            Node {
+                                                width: Val::Px(4.0),
+                                                height: Val::Px(0.0),
+                                                ..default()
+                                            },
+                                            BackgroundColor(Color::NONE),
+                                            PriceBar {
+                                                commodity: index,
+                                                sample,
+                                            },
+                                        ));
+                                    }
+                                });
+                            });
+                    }
+                    popup.spawn((Button, Resume)).with_children(|button| {
                         button.spawn((
-                            Text::new("Sell inventory"),
+                            Text::new("Resume"),
                             font_style.clone(),
                             TextColor(Color::WHITE),
                         ));
                     });
-                    popup.spawn((Button, Refill)).with_children(|button| {
+                    // One row per equipment slot: installed module and next upgrade
+                    for slot in EquipmentSlot::ALL {
+                        popup
+                            .spawn((
+                                Node {
+                                    flex_direction: FlexDirection::Row,
+                                    column_gap: Val::Px(20.0),
+                                    ..default()
+                                },
+                                ServiceSection(BuildingService::Upgrades),
+                            ))
+                            .with_children(|row| {
+                                row.spawn((Button, SwapModule(slot))).with_children(|button| {
+                                    button.spawn((
+                                        Text::default(),
+                                        font_style.clone(),
+                                        TextColor(Color::WHITE),
+                                    ));
+                                });
+                                row.spawn((Button, BuyModule(slot))).with_children(|button| {
+                                    button.spawn((
+                                        Text::default(),
+                                        font_style.clone(),
+                                        TextColor(Color::srgb(1.0, 0.8, 0.3)),
+                                    ));
+                                });
+                            });
+                    }
+                });
+            // Game over menu [index-2]
+            parent
+                .spawn((
+                    parent_node.clone(),
+                    BackgroundColor(Color::BLACK),
+                    Visibility::Hidden,
+                ))
+                .with_children(|popup| {
+                    popup.spawn((
+                        Text::new("Game Over"),
+                        font_style.clone(),
+                        TextColor(Color::WHITE),
+                    ));
+                    popup.spawn((
+                        Text::default(),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 14.0,
+                            ..Default::default()
+                        },
+                        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                        DamageLogText,
+                    ));
+                    popup.spawn((
+                        Text::default(),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 14.0,
+                            ..Default::default()
+                        },
+                        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                        RunStatsText,
+                    ));
+                    popup.spawn((Button, NewGame)).with_children(|button| {
                         button.spawn((
-                            Text::new("Refill tank"),
+                            Text::new("Restart game"),
                             font_style.clone(),
                             TextColor(Color::WHITE),
                         ));
                     });
-                    popup.spawn((Button, Resume)).with_children(|button| {
+                    popup.spawn((Button, QuitGame)).with_children(|button| {
                         button.spawn((
-                            Text::new("Resume"),
+                            Text::new("Exit game"),
+                            font_style.clone(),
+                            TextColor(Color::WHITE),
+                        ));
+                    });
+                });
+            // Rescue menu [index-3]
+            parent
+                .spawn((
+                    parent_node.clone(),
+                    BackgroundColor(Color::BLACK),
+                    Visibility::Hidden,
+                ))
+                .with_children(|popup| {
+                    popup.spawn((
+                        Text::new("Out of fuel"),
+                        font_style.clone(),
+                        TextColor(Color::WHITE),
+                    ));
+                    popup.spawn((
+                        Text::default(),
+                        font_style.clone(),
+                        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                        RescueText,
+                    ));
+                    popup.spawn((Button, CallTow)).with_children(|button| {
+                        button.spawn((
+                            Text::new("Pay for the tow"),
+                            font_style.clone(),
+                            TextColor(Color::WHITE),
+                        ));
+                    });
+                    popup.spawn((Button, ForfeitCargo)).with_children(|button| {
+                        button.spawn((
+                            Text::new("Pay with cargo"),
                             font_style.clone(),
                             TextColor(Color::WHITE),
                         ));
                     });
-                    popup.spawn((Button, UpgradeDrill)).with_children(|button| {
+                    popup.spawn((Button, GiveUp)).with_children(|button| {
                         button.spawn((
-                            Text::new("Upgrade Drill (80c)"),
+                            Text::new("Give up"),
                             font_style.clone(),
                             TextColor(Color::WHITE),
                         ));
                     });
-                    popup.spawn((Button, UpgradeSpeed)).with_children(|button| {
+                });
+            // Settings menu [index-4]
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Percent(60.0),
+                        height: Val::Percent(90.0),
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(20.0),
+                        top: Val::Percent(5.0),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::SpaceEvenly,
+                        padding: UiRect::all(Val::Px(20.)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::BLACK),
+                    Visibility::Hidden,
+                ))
+                .with_children(|popup| {
+                    popup.spawn((
+                        Text::new("Controls"),
+                        font_style.clone(),
+                        TextColor(Color::WHITE),
+                    ));
+                    popup.spawn((Button, SwitchBindingsPlayer)).with_children(|button| {
                         button.spawn((
-                            Text::new("Upgrade Speed (60c)"),
+                            Text::default(),
                             font_style.clone(),
                             TextColor(Color::WHITE),
                         ));
                     });
-                    popup.spawn((Button, UpgradeTank)).with_children(|button| {
+                    // One button per action, clicking it waits for the new key
+                    for action in Action::ALL {
+                        popup.spawn((Button, Rebind(action))).with_children(|button| {
+                            button.spawn((
+                                Text::default(),
+                                TextFont {
+                                    font: font.clone(),
+                                    font_size: 16.0,
+                                    ..Default::default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                        });
+                    }
+                    popup.spawn((Button, ResetBindings)).with_children(|button| {
                         button.spawn((
-                            Text::new("Upgrade Tank (100c)"),
+                            Text::new("Reset to defaults"),
                             font_style.clone(),
                             TextColor(Color::WHITE),
                         ));
                     });
-                    popup.spawn((Button, UpgradeArmor)).with_children(|button| {
+                    popup.spawn((Button, OpenStats)).with_children(|button| {
                         button.spawn((
-                            Text::new("Upgrade Armor (70c)"),
+                            Text::new("Statistics"),
+                            font_style.clone(),
+                            TextColor(Color::WHITE),
+                        ));
+                    });
+                    popup.spawn((Button, OpenAchievements)).with_children(|button| {
+                        button.spawn((
+                            Text::new("Achievements"),
+                            font_style.clone(),
+                            TextColor(Color::WHITE),
+                        ));
+                    });
+                    popup.spawn((Button, Back)).with_children(|button| {
+                        button.spawn((
+                            Text::new("Back"),
                             font_style.clone(),
                             TextColor(Color::WHITE),
                         ));
                     });
                 });
-            // Game over menu [index-2]
+            // Lifetime statistics menu [index-5]
             parent
                 .spawn((
                     parent_node.clone(),
@@ -196,25 +743,138 @@ pub fn init_menu(mut commands: Commands, assets_server: Res<AssetServer>) {
                 ))
                 .with_children(|popup| {
                     popup.spawn((
-                        Text::new("Game Over"),
+                        Text::new("Statistics"),
                         font_style.clone(),
                         TextColor(Color::WHITE),
                     ));
-                    popup.spawn((Button, NewGame)).with_children(|button| {
+                    popup.spawn((
+                        Text::default(),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 16.0,
+                            ..Default::default()
+                        },
+                        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                        LifetimeStatsText,
+                    ));
+                    popup.spawn((Button, Back)).with_children(|button| {
                         button.spawn((
-                            Text::new("Restart game"),
+                            Text::new("Back"),
                             font_style.clone(),
                             TextColor(Color::WHITE),
                         ));
                     });
-                    popup.spawn((Button, QuitGame)).with_children(|button| {
+                });
+            // Achievements menu [index-6]
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Percent(70.0),
+                        height: Val::Percent(80.0),
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(15.0),
+                        top: Val::Percent(10.0),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::SpaceEvenly,
+                        padding: UiRect::all(Val::Px(20.)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::BLACK),
+                    Visibility::Hidden,
+                ))
+                .with_children(|popup| {
+                    popup.spawn((
+                        Text::new("Achievements"),
+                        font_style.clone(),
+                        TextColor(Color::WHITE),
+                    ));
+                    popup.spawn((
+                        Text::default(),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 14.0,
+                            ..Default::default()
+                        },
+                        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                        AchievementsText,
+                    ));
+                    popup.spawn((Button, Back)).with_children(|button| {
                         button.spawn((
-                            Text::new("Exit game"),
+                            Text::new("Back"),
                             font_style.clone(),
                             TextColor(Color::WHITE),
                         ));
                     });
                 });
+            // Trading menu [index-7]
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Percent(70.0),
+                        height: Val::Percent(80.0),
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(15.0),
+                        top: Val::Percent(10.0),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::SpaceEvenly,
+                        padding: UiRect::all(Val::Px(20.)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::BLACK),
+                    Visibility::Hidden,
+                ))
+                .with_children(|popup| {
+                    popup.spawn((
+                        Text::new("Trade cargo"),
+                        font_style.clone(),
+                        TextColor(Color::WHITE),
+                    ));
+                    popup.spawn((
+                        Node {
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(8.0),
+                            ..default()
+                        },
+                        TradeList,
+                    ));
+                    popup.spawn((
+                        Text::default(),
+                        font_style.clone(),
+                        TextColor(Color::srgb(1.0, 0.8, 0.3)),
+                        TradeSummaryText,
+                    ));
+                    popup
+                        .spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            column_gap: Val::Px(20.0),
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn((Button, SellSelected)).with_children(|button| {
+                                button.spawn((
+                                    Text::new("Sell selected"),
+                                    font_style.clone(),
+                                    TextColor(Color::WHITE),
+                                ));
+                            });
+                            row.spawn((Button, Sell)).with_children(|button| {
+                                button.spawn((
+                                    Text::new("Sell all"),
+                                    font_style.clone(),
+                                    TextColor(Color::WHITE),
+                                ));
+                            });
+                            row.spawn((Button, CloseTrade)).with_children(|button| {
+                                button.spawn((
+                                    Text::new("Back"),
+                                    font_style.clone(),
+                                    TextColor(Color::WHITE),
+                                ));
+                            });
+                        });
+                });
         });
 }
 pub fn handle_start_menu(
@@ -233,11 +893,44 @@ pub fn handle_start_menu(
     }
 }
 
+/// Keeps only the parts of the base menu the visited building offers
+fn show_building_services(
+    mut sections: Query<(&ServiceSection, &mut Node, &mut Visibility)>,
+    settlement: Res<Settlement>,
+    visited_building: Res<VisitedBuilding>,
+) {
+    let building = &settlement.buildings[visited_building.0];
+    for (section, mut node, mut visibility) in sections.iter_mut() {
+        // Hidden alone would keep the space, and `Display::None` alone the gamepad focus
+        if building.offers(section.0) {
+            node.display = Display::Flex;
+            *visibility = Visibility::Inherited;
+        } else {
+            node.display = Display::None;
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
 pub fn handle_base_menu(
     menu_query: Query<(Entity, &Children), With<Menu>>,
     visibility_query: Query<&mut Visibility>,
+    mut base_title_text: Query<&mut Text, With<BaseTitleText>>,
+    menu_player: Res<MenuPlayer>,
+    coop: Res<CoopConfig>,
+    settlement: Res<Settlement>,
+    visited_building: Res<VisitedBuilding>,
 ) {
     info!("base menu");
+    // In co-op the title tells who is doing the shopping
+    if let Ok(mut text) = base_title_text.single_mut() {
+        let building = &settlement.buildings[visited_building.0].name;
+        text.0 = if coop.players > 1 {
+            format!("{} - {}", building, menu_player.0.name())
+        } else {
+            building.clone()
+        };
+    }
     if let Ok((entity, children)) = menu_query.single() {
         set_visibility_recursive(
             Visibility::Visible,
@@ -248,70 +941,157 @@ pub fn handle_base_menu(
         );
     }
 }
+#[allow(clippy::too_many_arguments)]
 fn handle_button_interaction(
-    interaction: Query<(&Interaction, &MenuButton), (Changed<Interaction>, With<Button>)>,
-    mut player: Query<(&mut Inventory, &mut Fuel, &mut Currency, &mut PlayerAttributes), With<Player>>,
+    buttons: Query<(Entity, Ref<Interaction>, &MenuButton), With<Button>>,
+    mut menu_focus: ResMut<MenuFocus>,
+    mut player: Query<
+        (
+            &PlayerId,
+            &mut Inventory,
+            &mut Fuel,
+            &mut Equipment,
+            &mut Transform,
+            &mut Velocity,
+            &mut Health,
+            &mut ToolBelt,
+            &mut RecallDevice,
+        ),
+        With<Player>,
+    >,
+    mut wallets: Query<(&PlayerId, &mut Currency), With<Player>>,
+    world_base: Query<&GlobalTransform, With<WorldBase>>,
     mut next_state: ResMut<NextState<GameState>>,
     economy: Res<EconomyConfig>,
+    catalog: Res<ModuleCatalog>,
     mut loading_progress: ResMut<LoadingProgress>,
     mut next_menu_state: ResMut<NextState<MenuState>>,
+    game_state: Res<State<GameState>>,
+    mut coop: ResMut<CoopConfig>,
+    menu_player: Res<MenuPlayer>,
+    mut player_bindings: ResMut<PlayerBindings>,
+    mut pending_rebind: ResMut<PendingRebind>,
+    (mut market, mut loot_sold_events, mut trade, host): (
+        ResMut<Market>,
+        EventWriter<LootSoldEvent>,
+        ResMut<TradeScreen>,
+        Option<Res<NetworkHost>>,
+    ),
 ) {
-    for (interaction, button) in interaction.iter() {
-        if *interaction == Pressed {
+    // Taken right away: the press may leave the menus, where nothing would clear it
+    let confirmed = menu_focus.confirmed.take();
+    for (entity, interaction, button) in buttons.iter() {
+        let clicked = interaction.is_changed() && *interaction == Pressed;
+        if clicked || confirmed == Some(entity) {
+            // The drill at the base (or stranded) and the wallet paying for it
+            let visitor = player
+                .iter_mut()
+                .find(|(player_id, ..)| **player_id == menu_player.0);
+            let wallet_id = coop.wallet_of(menu_player.0.0);
+            let wallet = wallets
+                .iter_mut()
+                .find(|(player_id, _)| player_id.0 == wallet_id)
+                .map(|(_, currency)| currency);
             match button {
-                Sell => {
-                    if let Ok((mut inventory, _, mut currency, _)) = player.single_mut() {
-                        sell_all_inventory(&mut inventory, &mut currency);
+                Sell | SellOne(_) | SellStack(_) | SellSelected => {
+                    if let (Some((_, mut inventory, ..)), Some(mut currency)) = (visitor, wallet) {
+                        let loot = match button {
+                            SellOne(id) => inventory.take(id, 1).into_iter().collect(),
+                            SellStack(id) => inventory.take(id, usize::MAX).into_iter().collect(),
+                            SellSelected => trade.take_selected(&mut inventory),
+                            _ => inventory.take_loot(),
+                        };
+                        let before = currency.amount;
+                        let items = sell_items(loot, &mut currency, &mut market);
+                        trade.earned += currency.amount - before;
+                        if !items.is_empty() {
+                            // Selling piece by piece still counts as one trip
+                            trade.sold.extend(items);
+                            loot_sold_events.write(LootSoldEvent {
+                                items: trade.sold.clone(),
+                            });
+                        }
+                    }
+                }
+                TradeQuantity(id, step) => {
+                    if let Some((_, inventory, ..)) = visitor {
+                        if let Some(item) = inventory.items.iter().find(|item| item.id == *id) {
+                            let selected = trade.selected_of(item) as i32 + step;
+                            let selected = selected.clamp(0, item.quantity as i32);
+                            trade.selected.insert(id.clone(), selected as usize);
+                        }
                     }
                 }
+                OpenTrade => {
+                    next_menu_state.set(MenuState::Trading);
+                }
+                CloseTrade => {
+                    next_menu_state.set(MenuState::WorldBase);
+                }
                 Refill => {
-                    if let Ok((_, mut fuel, mut currency, _)) = player.single_mut() {
+                    if let (Some((_, _, mut fuel, ..)), Some(mut currency)) = (visitor, wallet) {
                         refill_tank(&mut fuel, &mut currency, &economy);
                     }
                 }
-                UpgradeDrill => {
-                    if let Ok((_, _, mut currency, mut attributes)) = player.single_mut() {
-                        if currency.amount >= economy.upgrade_drill_cost {
-                            currency.amount -= economy.upgrade_drill_cost;
-                            attributes.drill_power += 0.5;
-                            info!("Upgraded drill power to {:.1}", attributes.drill_power);
+                Repair => {
+                    if let (Some((.., mut health, _, _)), Some(mut currency)) = (visitor, wallet) {
+                        repair_hull(&mut health, &mut currency, &economy);
+                    }
+                }
+                BuyDynamite => {
+                    if let (Some((.., mut tool_belt, _)), Some(mut currency)) = (visitor, wallet) {
+                        if tool_belt.dynamite >= tool_belt.max_dynamite {
+                            info!("Dynamite crate already full");
+                        } else if currency.amount >= economy.dynamite_cost {
+                            currency.amount -= economy.dynamite_cost;
+                            tool_belt.dynamite += 1;
+                            info!("Bought dynamite, {} in stock", tool_belt.dynamite);
                         }
                     }
                 }
-                UpgradeSpeed => {
-                    if let Ok((_, _, mut currency, mut attributes)) = player.single_mut() {
-                        if currency.amount >= economy.upgrade_speed_cost {
-                            currency.amount -= economy.upgrade_speed_cost;
-                            attributes.ground_speed_factor += 20.0;
-                            attributes.flying_speed_factor += 20.0;
-                            info!(
-                                "Upgraded speed to {:.1}",
-                                attributes.ground_speed_factor
-                            );
+                BuyConsumable(consumable) => {
+                    if let (Some((_, mut inventory, ..)), Some(mut currency)) = (visitor, wallet) {
+                        let cost = economy.consumable_cost(*consumable);
+                        if !inventory.has_room_for(1) {
+                            info!("Inventory full!");
+                        } else if currency.amount >= cost {
+                            currency.amount -= cost;
+                            inventory.add_item(consumable.to_item());
+                            info!("Bought {}", consumable.name());
                         }
                     }
                 }
-                UpgradeTank => {
-                    if let Ok((_, mut fuel, mut currency, _)) = player.single_mut() {
-                        if currency.amount >= economy.upgrade_tank_cost {
-                            currency.amount -= economy.upgrade_tank_cost;
-                            fuel.max += 50.0;
-                            info!("Upgraded tank to {:.1}", fuel.max);
+                BuyRecall => {
+                    if let (Some((.., mut recall_device)), Some(mut currency)) = (visitor, wallet) {
+                        if recall_device.owned {
+                            info!("Recall device already installed");
+                        } else if currency.amount >= economy.recall_device_cost {
+                            currency.amount -= economy.recall_device_cost;
+                            recall_device.owned = true;
+                            info!("Installed the recall device");
                         }
                     }
                 }
-                UpgradeArmor => {
-                    if let Ok((_, _, mut currency, mut attributes)) = player.single_mut() {
-                        if currency.amount >= economy.upgrade_armor_cost {
-                            currency.amount -= economy.upgrade_armor_cost;
-                            attributes.armor_resistance += 0.1;
-                            info!(
-                                "Upgraded armor resistance to {:.2}",
-                                attributes.armor_resistance
-                            );
+                BuyModule(slot) => {
+                    if let (Some((_, _, _, mut equipment, ..)), Some(mut currency)) =
+                        (visitor, wallet)
+                    {
+                        let Some(module) = catalog.next_upgrade(&equipment, *slot) else {
+                            info!("{} already fully upgraded", slot.name());
+                            continue;
+                        };
+                        if currency.amount >= module.cost {
+                            currency.amount -= module.cost;
+                            info!("Installed {} ({})", module.name, module.modifiers.describe());
+                            equipment.equip(module.clone());
                         }
                     }
                 }
+                SwapModule(slot) => {
+                    if let Some((_, _, _, mut equipment, ..)) = visitor {
+                        equipment.cycle(*slot);
+                    }
+                }
                 NewGame => {
                     loading_progress.rendering_map = false;
                     loading_progress.spawning_player = false;
@@ -327,6 +1107,85 @@ fn handle_button_interaction(
                     next_state.set(GameState::Playing);
                     next_menu_state.set(MenuState::None);
                 }
+                CallTow | ForfeitCargo => {
+                    let (
+                        Some((_, mut inventory, mut fuel, _, mut transform, mut velocity, ..)),
+                        Some(mut currency),
+                        Ok(base_transform),
+                    ) = (visitor, wallet, world_base.single())
+                    else {
+                        continue;
+                    };
+                    let price = tow_price(&economy, &transform, &inventory);
+                    let paid = if matches!(button, CallTow) {
+                        let paid = currency.amount >= price;
+                        if paid {
+                            currency.amount -= price;
+                        }
+                        paid
+                    } else {
+                        forfeit_cargo(&mut inventory, &market, price)
+                    };
+                    if !paid {
+                        info!("Cannot afford the tow this way");
+                        continue;
+                    }
+                    tow_to_base(
+                        &mut transform,
+                        &mut velocity,
+                        &mut fuel,
+                        base_transform.translation().truncate(),
+                    );
+                    next_state.set(GameState::Playing);
+                    next_menu_state.set(MenuState::None);
+                }
+                GiveUp => {
+                    next_menu_state.set(MenuState::GameOver);
+                }
+                OpenSettings => {
+                    next_menu_state.set(MenuState::Settings);
+                }
+                OpenStats => {
+                    next_menu_state.set(MenuState::Stats);
+                }
+                OpenAchievements => {
+                    next_menu_state.set(MenuState::Achievements);
+                }
+                Back => {
+                    if *game_state.get() == GameState::MainMenu {
+                        next_menu_state.set(MenuState::Start);
+                    } else {
+                        next_state.set(GameState::Playing);
+                        next_menu_state.set(MenuState::None);
+                    }
+                }
+                Rebind(action) => {
+                    info!("Press a key for {}, Escape to cancel", action.name());
+                    pending_rebind.action = Some(*action);
+                }
+                ResetBindings => {
+                    let bindings_player = pending_rebind.player;
+                    player_bindings.players[bindings_player] =
+                        KeyBindings::default_for(bindings_player);
+                    player_bindings.players[bindings_player].save(bindings_player);
+                    pending_rebind.action = None;
+                }
+                SwitchBindingsPlayer => {
+                    pending_rebind.player = (pending_rebind.player + 1) % CoopConfig::MAX_PLAYERS;
+                    pending_rebind.action = None;
+                }
+                TogglePlayers => {
+                    // Online players took their slots when they joined
+                    if host.as_ref().is_some_and(|host| host.has_clients()) {
+                        info!("Players joined online, the crew size is fixed");
+                        continue;
+                    }
+                    coop.players = coop.players % CoopConfig::MAX_PLAYERS + 1;
+                    info!("{} player(s)", coop.players);
+                }
+                ToggleWallet => {
+                    coop.shared_wallet = !coop.shared_wallet;
+                }
                 QuitGame => {
                     next_state.set(GameState::GameOver);
                     next_menu_state.set(MenuState::None);
@@ -336,7 +1195,161 @@ fn handle_button_interaction(
     }
 }
 
-fn refill_tank(fuel: &mut Fuel, currency: &mut Currency, economy_config: &Res<EconomyConfig>) {
+/// Moves the focus between the visible buttons with the d-pad or the stick, South presses it
+fn navigate_menu_with_gamepad(
+    gamepads: Query<&Gamepad>,
+    mut buttons: Query<
+        (Entity, &GlobalTransform, &InheritedVisibility, &mut BackgroundColor),
+        With<MenuButton>,
+    >,
+    mut menu_focus: ResMut<MenuFocus>,
+    mut previous_stick: Local<i32>,
+) {
+    let mut visible: Vec<(Entity, Vec2)> = buttons
+        .iter()
+        .filter(|(_, _, visibility, _)| visibility.get())
+        .map(|(entity, transform, _, _)| (entity, transform.translation().truncate()))
+        .collect();
+    // Reading order: top to bottom, then left to right
+    visible.sort_by(|a, b| a.1.y.total_cmp(&b.1.y).then(a.1.x.total_cmp(&b.1.x)));
+
+    let mut step = 0;
+    let mut confirm = false;
+    for gamepad in gamepads.iter() {
+        if gamepad.any_just_pressed([GamepadButton::DPadDown, GamepadButton::DPadRight]) {
+            step += 1;
+        }
+        if gamepad.any_just_pressed([GamepadButton::DPadUp, GamepadButton::DPadLeft]) {
+            step -= 1;
+        }
+        confirm |= gamepad.just_pressed(GamepadButton::South);
+    }
+    // A stick flick moves one button, like a d-pad press
+    let stick = gamepads
+        .iter()
+        .map(|gamepad| gamepad.left_stick().y)
+        .find(|y| y.abs() > 0.5)
+        .map_or(0, |y| -(y.signum() as i32));
+    if stick != *previous_stick {
+        step += stick;
+        *previous_stick = stick;
+    }
+
+    let current = menu_focus
+        .button
+        .and_then(|focus| visible.iter().position(|(entity, _)| *entity == focus));
+    menu_focus.button = match current {
+        Some(index) => {
+            let next = (index as i32 + step).rem_euclid(visible.len() as i32);
+            Some(visible[next as usize].0)
+        }
+        None if step != 0 || confirm => visible.first().map(|(entity, _)| *entity),
+        None => None,
+    };
+    if confirm && current.is_some() {
+        menu_focus.confirmed = menu_focus.button;
+    }
+
+    for (entity, _, _, mut background) in buttons.iter_mut() {
+        let color = if menu_focus.button == Some(entity) {
+            Color::srgb(0.25, 0.25, 0.25)
+        } else {
+            Color::NONE
+        };
+        background.set_if_neq(BackgroundColor(color));
+    }
+}
+
+/// Labels the equipment rows with the installed module and the next one for sale
+fn update_module_buttons(
+    buttons: Query<(&MenuButton, &Children)>,
+    mut texts: Query<&mut Text>,
+    player: Query<(&PlayerId, &Equipment), With<Player>>,
+    catalog: Res<ModuleCatalog>,
+    menu_player: Res<MenuPlayer>,
+) {
+    let Some((_, equipment)) = player
+        .iter()
+        .find(|(player_id, _)| **player_id == menu_player.0)
+    else {
+        return;
+    };
+    for (button, children) in buttons.iter() {
+        let label = match button {
+            SwapModule(slot) => {
+                let installed = equipment.equipped(*slot).map_or("-", |m| m.name.as_str());
+                let stored = equipment.stash.iter().filter(|m| m.slot == *slot).count();
+                if stored > 0 {
+                    format!("{}: {} ({} stored)", slot.name(), installed, stored)
+                } else {
+                    format!("{}: {}", slot.name(), installed)
+                }
+            }
+            BuyModule(slot) => match catalog.next_upgrade(equipment, *slot) {
+                Some(module) => format!(
+                    "Buy {} ({}c): {}",
+                    module.name,
+                    module.cost,
+                    module.modifiers.describe()
+                ),
+                None => "Fully upgraded".to_string(),
+            },
+            _ => continue,
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                if text.0 != label {
+                    text.0 = label.clone();
+                }
+            }
+        }
+    }
+}
+
+/// Draws the price history of every commodity, green above its base price and red below
+fn update_price_chart(
+    market: Res<Market>,
+    mut labels: Query<(&mut Text, &PriceLabel)>,
+    mut news_text: Query<&mut Text, (With<MarketNewsText>, Without<PriceLabel>)>,
+    mut bars: Query<(&mut Node, &mut BackgroundColor, &PriceBar)>,
+) {
+    if !market.is_changed() {
+        return;
+    }
+    if let Ok(mut text) = news_text.single_mut() {
+        text.0 = match &market.event {
+            Some((kind, _)) => format!("{}: {}", kind.name, kind.description),
+            None => "Market is calm".to_string(),
+        };
+    }
+    for (mut text, label) in labels.iter_mut() {
+        let commodity = &market.commodities[label.0];
+        let price = market.price(&commodity.id).unwrap_or(commodity.base_price);
+        text.0 = format!("{} {}c (base {}c)", commodity.name, price, commodity.base_price);
+    }
+    for (mut node, mut background, bar) in bars.iter_mut() {
+        let commodity = &market.commodities[bar.commodity];
+        // Samples are right-aligned, the newest one is the last column
+        let missing = HISTORY_LEN - commodity.history.len();
+        let Some(price) = bar
+            .sample
+            .checked_sub(missing)
+            .and_then(|sample| commodity.history.get(sample))
+        else {
+            node.height = Val::Px(0.0);
+            continue;
+        };
+        let scale = (*price as f32 / (commodity.base_price as f32 * 2.0)).min(1.0);
+        node.height = Val::Px((scale * PRICE_CHART_HEIGHT).max(1.0));
+        background.0 = if *price >= commodity.base_price {
+            Color::srgb(0.3, 0.8, 0.3)
+        } else {
+            Color::srgb(0.8, 0.3, 0.3)
+        };
+    }
+}
+
+pub fn refill_tank(fuel: &mut Fuel, currency: &mut Currency, economy_config: &Res<EconomyConfig>) {
     info!("Refill tank");
     let fuel_needed = fuel.max - fuel.current;
     let refill_cost = if fuel_needed >= 90.0 {
@@ -356,22 +1369,190 @@ fn refill_tank(fuel: &mut Fuel, currency: &mut Currency, economy_config: &Res<Ec
     fuel.current += refilled;
 }
 
-fn sell_all_inventory(inventory: &mut Inventory, currency: &mut Currency) {
-    if inventory.is_empty() {
-        info!("No items to be sold");
+/// Mends as much of the hull as the wallet pays for
+pub fn repair_hull(health: &mut Health, currency: &mut Currency, economy_config: &EconomyConfig) {
+    let damage = (health.max - health.current).max(0.0);
+    let affordable = (currency.amount / economy_config.repair_cost_per_point.max(1)) as f32;
+    let repaired = damage.min(affordable).floor();
+    if repaired <= 0.0 {
+        info!("Nothing to repair");
         return;
     }
-    let total_to_sell: u32 = inventory
-        .items
-        .iter()
-        .map(|i| i.value * i.quantity as u32)
-        .sum();
+    let cost = repaired as u32 * economy_config.repair_cost_per_point;
+    info!("Repaired {} hull points spending: {}", repaired, cost);
+    currency.amount -= cost;
+    health.current += repaired;
+}
+
+pub fn sell_all_inventory(
+    inventory: &mut Inventory,
+    currency: &mut Currency,
+    market: &mut Market,
+) -> Vec<Item> {
+    // Consumables stay on board
+    sell_items(inventory.take_loot(), currency, market)
+}
+
+/// Sells the given stacks at market price and returns them, valued at what they fetched
+pub fn sell_items(mut loot: Vec<Item>, currency: &mut Currency, market: &mut Market) -> Vec<Item> {
+    if loot.is_empty() {
+        info!("No items to be sold");
+        return loot;
+    }
+    let mut total_to_sell = 0;
+    for item in loot.iter_mut() {
+        // The market sets the price, items it does not trade go at face value
+        let earned = market
+            .sell(&item.id, item.quantity)
+            .unwrap_or(item.value * item.quantity as u32);
+        item.value = earned / item.quantity.max(1) as u32;
+        total_to_sell += earned;
+    }
 
     info!("Total earned: {}", total_to_sell);
     currency.add_amount(total_to_sell);
-    inventory.clear();
-    info!("Inventory {:?}", inventory.items);
     info!("Currency: {}", currency.amount);
+    loot
+}
+
+fn reset_trade_screen(mut trade: ResMut<TradeScreen>) {
+    *trade = TradeScreen::default();
+}
+
+pub fn handle_trade_menu(
+    menu_query: Query<(Entity, &Children), With<Menu>>,
+    visibility_query: Query<&mut Visibility>,
+    mut trade: ResMut<TradeScreen>,
+) {
+    info!("Trading menu");
+    // Rebuild the rows, the cargo may have changed since the screen was last open
+    trade.set_changed();
+    if let Ok((entity, children)) = menu_query.single() {
+        set_visibility_recursive(
+            Visibility::Visible,
+            entity,
+            children,
+            Some(7),
+            visibility_query,
+        );
+    }
+}
+
+/// One row per cargo stack: unit price, quantity picker and sell buttons, then the totals
+#[allow(clippy::too_many_arguments)]
+fn update_trade_list(
+    mut commands: Commands,
+    assets_server: Res<AssetServer>,
+    trade_list: Query<Entity, With<TradeList>>,
+    mut summary_text: Query<&mut Text, With<TradeSummaryText>>,
+    player: Query<(&PlayerId, Ref<Inventory>), With<Player>>,
+    cells: Query<&TradeCell>,
+    menu_player: Res<MenuPlayer>,
+    market: Res<Market>,
+    trade: Res<TradeScreen>,
+    mut menu_focus: ResMut<MenuFocus>,
+) {
+    let Some((_, inventory)) = player
+        .iter()
+        .find(|(player_id, _)| **player_id == menu_player.0)
+    else {
+        return;
+    };
+    if !inventory.is_changed() && !market.is_changed() && !trade.is_changed() {
+        return;
+    }
+    let Ok(list) = trade_list.single() else {
+        return;
+    };
+    let font_style = TextFont {
+        font: assets_server.load("fonts/FiraSans-Regular.ttf"),
+        font_size: 16.0,
+        ..Default::default()
+    };
+    let loot: Vec<&Item> = inventory
+        .items
+        .iter()
+        .filter(|item| item.consumable.is_none())
+        .collect();
+    let mut selected_total = 0;
+    // The focused button is rebuilt too: the focus moves to the one in its place
+    let focused_cell = menu_focus
+        .button
+        .and_then(|entity| cells.get(entity).ok())
+        .map(|cell| (cell.0.min(loot.len().saturating_sub(1)), cell.1));
+    let mut refocus = None;
+    commands.entity(list).despawn_related::<Children>();
+    commands.entity(list).with_children(|list| {
+        if loot.is_empty() {
+            list.spawn((
+                Text::new("No cargo to sell"),
+                font_style.clone(),
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            ));
+        }
+        for (row_index, item) in loot.into_iter().enumerate() {
+            let selected = trade.selected_of(item);
+            let unit_price = market.price(&item.id).unwrap_or(item.value);
+            selected_total += market
+                .quote(&item.id, selected)
+                .unwrap_or(item.value * selected as u32);
+            list.spawn(Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(12.0),
+                ..default()
+            })
+            .with_children(|row| {
+                row.spawn((
+                    Text::new(format!("{} x{} at {}c", item.name, item.quantity, unit_price)),
+                    font_style.clone(),
+                    TextColor(Color::WHITE),
+                    Node {
+                        width: Val::Px(180.0),
+                        ..default()
+                    },
+                ));
+                let buttons = [
+                    ("-", TradeQuantity(item.id.clone(), -1)),
+                    ("+", TradeQuantity(item.id.clone(), 1)),
+                    ("Sell one", SellOne(item.id.clone())),
+                    ("Sell stack", SellStack(item.id.clone())),
+                ];
+                for (column, (label, button)) in buttons.into_iter().enumerate() {
+                    // The picked quantity sits between its "-" and "+"
+                    if matches!(button, TradeQuantity(_, 1)) {
+                        row.spawn((
+                            Text::new(selected.to_string()),
+                            font_style.clone(),
+                            TextColor(Color::srgb(1.0, 0.8, 0.3)),
+                        ));
+                    }
+                    let button = row
+                        .spawn((Button, button, TradeCell(row_index, column)))
+                        .with_children(|button| {
+                            button.spawn((
+                                Text::new(label),
+                                font_style.clone(),
+                                TextColor(Color::WHITE),
+                            ));
+                        })
+                        .id();
+                    if focused_cell == Some((row_index, column)) {
+                        refocus = Some(button);
+                    }
+                }
+            });
+        }
+    });
+    if focused_cell.is_some() {
+        menu_focus.button = refocus;
+    }
+    if let Ok(mut text) = summary_text.single_mut() {
+        text.0 = format!(
+            "Selected: {}c    Earned this visit: {}c",
+            selected_total, trade.earned
+        );
+    }
 }
 
 pub fn handle_inventory_menu() {
@@ -379,16 +1560,140 @@ pub fn handle_inventory_menu() {
     //TODO implementation
 }
 
-pub fn handle_settings_menu() {
+pub fn handle_settings_menu(
+    menu_query: Query<(Entity, &Children), With<Menu>>,
+    visibility_query: Query<&mut Visibility>,
+) {
     info!("Settings menu");
-    //TODO implementation
+    if let Ok((entity, children)) = menu_query.single() {
+        set_visibility_recursive(
+            Visibility::Visible,
+            entity,
+            children,
+            Some(4),
+            visibility_query,
+        );
+    }
+}
+
+fn pause_game(
+    actions: Res<ButtonInput<Action>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+) {
+    if actions.just_pressed(Action::Pause) {
+        next_state.set(GameState::Menu);
+        next_menu_state.set(MenuState::Settings);
+    }
+}
+
+/// Binds the next key pressed to the action picked in the settings menu and saves the bindings
+fn capture_rebind(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut pending_rebind: ResMut<PendingRebind>,
+    mut player_bindings: ResMut<PlayerBindings>,
+) {
+    let Some(action) = pending_rebind.action else {
+        return;
+    };
+    let Some(key) = keyboard_input.get_just_pressed().next().copied() else {
+        return;
+    };
+    pending_rebind.action = None;
+    if key == KeyCode::Escape {
+        info!("Rebinding {} cancelled", action.name());
+        return;
+    }
+    if !is_bindable(key) {
+        info!("{:?} cannot be bound", key);
+        return;
+    }
+    let player = pending_rebind.player;
+    player_bindings.players[player].rebind(action, key);
+    info!(
+        "{} bound to {} for {}",
+        action.name(),
+        key_label(key),
+        PlayerId(player).name()
+    );
+    player_bindings.players[player].save(player);
+}
+
+fn cancel_rebind(mut pending_rebind: ResMut<PendingRebind>) {
+    pending_rebind.action = None;
+}
+
+fn update_binding_buttons(
+    buttons: Query<(&MenuButton, &Children)>,
+    mut texts: Query<&mut Text>,
+    player_bindings: Res<PlayerBindings>,
+    pending_rebind: Res<PendingRebind>,
+) {
+    let key_bindings = player_bindings.get(pending_rebind.player);
+    for (button, children) in buttons.iter() {
+        let label = match button {
+            Rebind(action) if pending_rebind.action == Some(*action) => {
+                format!("{}: press a key...", action.name())
+            }
+            Rebind(action) => format!("{}: {}", action.name(), key_bindings.describe(*action)),
+            SwitchBindingsPlayer => format!("Editing: {}", PlayerId(pending_rebind.player).name()),
+            _ => continue,
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                if text.0 != label {
+                    text.0 = label.clone();
+                }
+            }
+        }
+    }
+}
+
+/// Labels the co-op toggles of the main menu with the current choice
+fn update_coop_buttons(
+    buttons: Query<(&MenuButton, &Children)>,
+    mut texts: Query<&mut Text>,
+    coop: Res<CoopConfig>,
+) {
+    for (button, children) in buttons.iter() {
+        let label = match button {
+            TogglePlayers => format!("Players: {}", coop.players),
+            ToggleWallet if coop.shared_wallet => "Wallet: shared".to_string(),
+            ToggleWallet => "Wallet: separate".to_string(),
+            _ => continue,
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                if text.0 != label {
+                    text.0 = label.clone();
+                }
+            }
+        }
+    }
 }
 
 pub fn handle_gameover_menu(
     menu_query: Query<(Entity, &Children), With<Menu>>,
     visibility_query: Query<&mut Visibility>,
+    mut damage_log_text: Query<&mut Text, (With<DamageLogText>, Without<RunStatsText>)>,
+    mut run_stats_text: Query<&mut Text, With<RunStatsText>>,
+    damage_log: Res<DamageLog>,
+    run_stats: Res<RunStats>,
 ) {
     info!("Game over menu");
+    if let Ok(mut text) = damage_log_text.single_mut() {
+        text.0 = describe_damage_log(&damage_log);
+    }
+    // Two statistics per line to fit next to the damage log
+    if let Ok(mut text) = run_stats_text.single_mut() {
+        text.0 = run_stats
+            .totals
+            .describe()
+            .chunks(2)
+            .map(|pair| pair.join("   "))
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
     if let Ok((entity, children)) = menu_query.single() {
         set_visibility_recursive(
             Visibility::Visible,
@@ -400,6 +1705,183 @@ pub fn handle_gameover_menu(
     }
 }
 
+pub fn handle_stats_menu(
+    menu_query: Query<(Entity, &Children), With<Menu>>,
+    visibility_query: Query<&mut Visibility>,
+    mut lifetime_stats_text: Query<&mut Text, With<LifetimeStatsText>>,
+    lifetime_stats: Res<LifetimeStats>,
+) {
+    info!("Statistics menu");
+    if let Ok(mut text) = lifetime_stats_text.single_mut() {
+        text.0 = lifetime_stats.describe().join("\n");
+    }
+    if let Ok((entity, children)) = menu_query.single() {
+        set_visibility_recursive(
+            Visibility::Visible,
+            entity,
+            children,
+            Some(5),
+            visibility_query,
+        );
+    }
+}
+
+pub fn handle_achievements_menu(
+    menu_query: Query<(Entity, &Children), With<Menu>>,
+    visibility_query: Query<&mut Visibility>,
+    mut achievements_text: Query<&mut Text, With<AchievementsText>>,
+    achievements: Res<Achievements>,
+    run_stats: Res<RunStats>,
+    lifetime_stats: Res<LifetimeStats>,
+) {
+    info!("Achievements menu");
+    if let Ok(mut text) = achievements_text.single_mut() {
+        let totals = combined_totals(&run_stats, &lifetime_stats);
+        text.0 = achievements.describe(&totals).join("\n");
+    }
+    if let Ok((entity, children)) = menu_query.single() {
+        set_visibility_recursive(
+            Visibility::Visible,
+            entity,
+            children,
+            Some(6),
+            visibility_query,
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_rescue_menu(
+    menu_query: Query<(Entity, &Children), With<Menu>>,
+    visibility_query: Query<&mut Visibility>,
+    mut rescue_text: Query<&mut Text, With<RescueText>>,
+    player: Query<(&PlayerId, &Transform, &Inventory), With<Player>>,
+    wallets: Query<(&PlayerId, &Currency), With<Player>>,
+    economy: Res<EconomyConfig>,
+    market: Res<Market>,
+    menu_player: Res<MenuPlayer>,
+    coop: Res<CoopConfig>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+) {
+    info!("Rescue menu");
+    let wallet_id = coop.wallet_of(menu_player.0.0);
+    let (Some((_, transform, inventory)), Some((_, currency))) = (
+        player
+            .iter()
+            .find(|(player_id, ..)| **player_id == menu_player.0),
+        wallets.iter().find(|(player_id, _)| player_id.0 == wallet_id),
+    ) else {
+        return;
+    };
+    let price = tow_price(&economy, transform, inventory);
+    let cargo_value = loot_value(inventory, &market);
+    if currency.amount < price && cargo_value < price {
+        info!("Tow costs {}c, cannot afford it", price);
+        next_menu_state.set(MenuState::GameOver);
+        return;
+    }
+    if let Ok(mut text) = rescue_text.single_mut() {
+        let towed = if coop.players > 1 {
+            format!("Tow {} to the base", menu_player.0.name())
+        } else {
+            "Tow to the base".to_string()
+        };
+        text.0 = format!(
+            "{}: {}c (you have {}c, cargo worth {}c)",
+            towed, price, currency.amount, cargo_value
+        );
+    }
+    if let Ok((entity, children)) = menu_query.single() {
+        set_visibility_recursive(
+            Visibility::Visible,
+            entity,
+            children,
+            Some(3),
+            visibility_query,
+        );
+    }
+}
+
+pub fn tow_price(economy: &EconomyConfig, transform: &Transform, inventory: &Inventory) -> u32 {
+    let depth = -world_to_grid_position(transform.translation.truncate()).1;
+    economy.tow_price(depth, inventory.total_mass())
+}
+
+/// Drops the drill on the base with enough fuel to reach the pump
+pub fn tow_to_base(
+    transform: &mut Transform,
+    velocity: &mut Velocity,
+    fuel: &mut Fuel,
+    base_position: Vec2,
+) {
+    info!("Towed back to the base");
+    transform.translation = base_position.extend(transform.translation.z);
+    *velocity = Velocity::zero();
+    fuel.current = TOW_FUEL.min(fuel.max);
+}
+
+/// What the cargo would fetch at the current market prices
+fn loot_value(inventory: &Inventory, market: &Market) -> u32 {
+    inventory
+        .items
+        .iter()
+        .filter(|i| i.consumable.is_none())
+        .map(|i| market.price(&i.id).unwrap_or(i.value) * i.quantity as u32)
+        .sum()
+}
+
+/// Hands over cargo, cheapest first at market price, until its value covers the price
+pub fn forfeit_cargo(inventory: &mut Inventory, market: &Market, price: u32) -> bool {
+    if loot_value(inventory, market) < price {
+        return false;
+    }
+    let mut forfeited = 0;
+    while forfeited < price {
+        let Some((id, value)) = inventory
+            .items
+            .iter()
+            .filter(|i| i.consumable.is_none())
+            .map(|i| (&i.id, market.price(&i.id).unwrap_or(i.value)))
+            .min_by_key(|(_, value)| *value)
+            .map(|(id, value)| (id.clone(), value))
+        else {
+            break;
+        };
+        inventory.remove_one(&id);
+        forfeited += value;
+    }
+    info!("Forfeited cargo worth {}c", forfeited);
+    true
+}
+
+/// Last blow first, then the total taken from each source
+fn describe_damage_log(damage_log: &DamageLog) -> String {
+    let Some(last) = damage_log.records.last() else {
+        return "No damage taken".to_string();
+    };
+    let mut totals: Vec<(&str, DamageType, f32)> = Vec::new();
+    for record in &damage_log.records {
+        let total = totals.iter_mut().find(|(source, damage_type, _)| {
+            *source == record.source && *damage_type == record.damage_type
+        });
+        match total {
+            Some(total) => total.2 += record.amount,
+            None => totals.push((record.source, record.damage_type, record.amount)),
+        }
+    }
+    totals.sort_by(|a, b| b.2.total_cmp(&a.2));
+    let mut lines = vec![format!(
+        "Last hit: {:.0} {} damage from {}",
+        last.amount,
+        last.damage_type.name(),
+        last.source
+    )];
+    lines.extend(totals.into_iter().map(|(source, damage_type, amount)| {
+        format!("{}: {:.0} {} damage", source, amount, damage_type.name())
+    }));
+    lines.join("\n")
+}
+
 fn cleanup_menu(
     menu_query: Query<(Entity, &Children), With<Menu>>,
     visibility_query: Query<&mut Visibility>,
@@ -0,0 +1,398 @@
+use crate::controls::{Action, PlayerActions};
+use crate::game::GameState;
+use crate::game::GameSystems::Ui;
+use crate::map::{GRID_HEIGHT, GRID_WIDTH, TileType, WorldGrid, world_to_grid_position};
+use crate::player::{Player, PlayerId};
+use crate::prelude::{Building, MenuState, Salvage};
+use bevy::asset::RenderAssetUsages;
+use bevy::image::ImageSampler;
+use bevy::input::mouse::MouseWheel;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+/// Right stick deflection below this does not zoom the full map
+const STICK_ZOOM_DEADZONE: f32 = 0.2;
+/// Number of tiles shown on each side of the HUD minimap
+const MINIMAP_TILES: f32 = 41.0;
+const MINIMAP_SIZE: f32 = 164.0;
+const MINIMAP_REFRESH_SECS: f32 = 0.2;
+const MAP_MIN_ZOOM: f32 = 1.0;
+const MAP_MAX_ZOOM: f32 = 8.0;
+const MAP_PAN_SPEED: f32 = 60.0;
+
+pub struct MinimapPlugin;
+
+/// Explored map texture: one pixel per tile, rows going from the surface downwards.
+#[derive(Resource)]
+pub struct MinimapImage {
+    pub handle: Handle<Image>,
+    refresh: Timer,
+}
+
+/// Points of interest placed by the player, shown on both maps.
+#[derive(Resource, Default)]
+pub struct MapMarkers {
+    pub positions: Vec<(i32, i32)>,
+}
+
+/// Portion of the explored map shown by the full-screen map, in texture pixels.
+#[derive(Resource)]
+struct MapView {
+    center: Vec2,
+    zoom: f32,
+}
+
+impl Default for MapView {
+    fn default() -> Self {
+        Self {
+            center: Vec2::new(GRID_WIDTH as f32 / 2.0, 0.0),
+            zoom: 2.0,
+        }
+    }
+}
+
+#[derive(Component)]
+struct HudMinimap;
+
+#[derive(Component)]
+struct FullMap;
+
+#[derive(Component)]
+struct FullMapImage;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MapMarkers>()
+            .init_resource::<MapView>()
+            .add_systems(OnEnter(GameState::Rendering), init_minimap)
+            .add_systems(
+                Update,
+                (toggle_map, place_marker, paint_minimap, update_minimap_view)
+                    .chain()
+                    .in_set(Ui)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnEnter(MenuState::Map), show_full_map)
+            .add_systems(OnExit(MenuState::Map), hide_full_map)
+            .add_systems(
+                Update,
+                (close_map, pan_zoom_map)
+                    .in_set(Ui)
+                    .run_if(in_state(MenuState::Map)),
+            );
+    }
+}
+
+fn init_minimap(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut markers: ResMut<MapMarkers>,
+    minimap_query: Query<Entity, With<HudMinimap>>,
+    full_map_query: Query<Entity, With<FullMap>>,
+) {
+    //Clear previous maps, if any
+    for entity in minimap_query.iter().chain(full_map_query.iter()) {
+        commands.entity(entity).despawn();
+    }
+    markers.positions.clear();
+
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: GRID_WIDTH as u32,
+            height: GRID_HEIGHT as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    let handle = images.add(image);
+
+    // Minimap, top right corner of the HUD
+    commands.spawn((
+        HudMinimap,
+        ImageNode {
+            image: handle.clone(),
+            rect: Some(Rect::new(0.0, 0.0, MINIMAP_TILES, MINIMAP_TILES)),
+            ..default()
+        },
+        Node {
+            width: Val::Px(MINIMAP_SIZE),
+            height: Val::Px(MINIMAP_SIZE),
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            top: Val::Px(10.0),
+            border: UiRect::all(Val::Px(2.0)),
+            ..default()
+        },
+        BorderColor(Color::srgb(0.6, 0.6, 0.6)),
+    ));
+
+    // Full-screen map, shown on MenuState::Map
+    commands
+        .spawn((
+            FullMap,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                FullMapImage,
+                ImageNode {
+                    image: handle.clone(),
+                    ..default()
+                },
+                Node {
+                    width: Val::Vh(85.0),
+                    height: Val::Vh(85.0),
+                    border: UiRect::all(Val::Px(2.0)),
+                    ..default()
+                },
+                BorderColor(Color::srgb(0.6, 0.6, 0.6)),
+            ));
+        });
+
+    commands.insert_resource(MinimapImage {
+        handle,
+        refresh: Timer::from_seconds(MINIMAP_REFRESH_SECS, TimerMode::Repeating),
+    });
+}
+
+fn toggle_map(
+    actions: Res<ButtonInput<Action>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+) {
+    if actions.just_pressed(Action::Map) {
+        next_state.set(GameState::Menu);
+        next_menu_state.set(MenuState::Map);
+    }
+}
+
+fn close_map(
+    actions: Res<ButtonInput<Action>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+) {
+    if actions.any_just_pressed([Action::Map, Action::Pause]) {
+        next_state.set(GameState::Playing);
+        next_menu_state.set(MenuState::None);
+    }
+}
+
+fn place_marker(
+    player: Query<(&Transform, &PlayerActions), With<Player>>,
+    mut markers: ResMut<MapMarkers>,
+) {
+    // Markers are shared, each drill places them where it stands
+    for (transform, actions) in player.iter() {
+        if !actions.buttons.just_pressed(Action::PlaceMarker) {
+            continue;
+        }
+        let position = world_to_grid_position(transform.translation.truncate());
+        // Placing a marker on an existing one removes it
+        if let Some(index) = markers.positions.iter().position(|p| *p == position) {
+            markers.positions.remove(index);
+            info!("Removed map marker at {:?}", position);
+        } else {
+            markers.positions.push(position);
+            info!("Placed map marker at {:?}", position);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn paint_minimap(
+    time: Res<Time>,
+    mut minimap: ResMut<MinimapImage>,
+    mut images: ResMut<Assets<Image>>,
+    world_grid: Res<WorldGrid>,
+    markers: Res<MapMarkers>,
+    player: Query<(&Transform, &PlayerId), With<Player>>,
+    buildings: Query<&GlobalTransform, With<Building>>,
+    salvage: Query<&Transform, With<Salvage>>,
+) {
+    if !minimap.refresh.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Some(image) = images.get_mut(&minimap.handle) else {
+        return;
+    };
+    let Some(data) = image.data.as_mut() else {
+        return;
+    };
+
+    for (row, tiles_row) in world_grid.tiles.iter().rev().enumerate() {
+        for (column, tile_type) in tiles_row.iter().enumerate() {
+            let position = (
+                column as i32 - (GRID_WIDTH / 2) as i32,
+                -(row as i32) - 1,
+            );
+            let color = if world_grid.revealed_tiles.contains(&position) {
+                tile_color(tile_type)
+            } else {
+                [0, 0, 0, 255]
+            };
+            write_pixel(data, column as i32, row as i32, color);
+        }
+    }
+
+    for building_transform in buildings.iter() {
+        let building = world_to_grid_position(building_transform.translation().truncate());
+        paint_cross(data, building, [60, 220, 60, 255]);
+    }
+    for marker in markers.positions.iter() {
+        paint_cross(data, *marker, [230, 40, 40, 255]);
+    }
+    if let Ok(salvage_transform) = salvage.single() {
+        let wreck = world_to_grid_position(salvage_transform.translation.truncate());
+        paint_cross(data, wreck, [240, 160, 40, 255]);
+    }
+    for (transform, player_id) in player.iter() {
+        let position = world_to_grid_position(transform.translation.truncate());
+        paint_cross(data, position, player_id.color().to_srgba().to_u8_array());
+    }
+}
+
+fn update_minimap_view(
+    player: Query<&Transform, With<Player>>,
+    mut minimap: Query<&mut ImageNode, With<HudMinimap>>,
+) {
+    if let (Some(crew), Ok(mut image_node)) = (crew_center(&player), minimap.single_mut()) {
+        let position = world_to_grid_position(crew);
+        let center = grid_to_pixel(position).as_vec2() + Vec2::splat(0.5);
+        image_node.rect = Some(view_rect(center, MINIMAP_TILES));
+    }
+}
+
+fn show_full_map(
+    player: Query<&Transform, With<Player>>,
+    mut map_view: ResMut<MapView>,
+    mut full_map: Query<&mut Visibility, With<FullMap>>,
+    mut full_map_image: Query<&mut ImageNode, With<FullMapImage>>,
+) {
+    info!("Map menu");
+    if let Some(crew) = crew_center(&player) {
+        let position = world_to_grid_position(crew);
+        map_view.center = grid_to_pixel(position).as_vec2();
+    }
+    if let Ok(mut visibility) = full_map.single_mut() {
+        *visibility = Visibility::Visible;
+    }
+    if let Ok(mut image_node) = full_map_image.single_mut() {
+        image_node.rect = Some(view_rect(map_view.center, map_view_size(map_view.zoom)));
+    }
+}
+
+fn hide_full_map(mut full_map: Query<&mut Visibility, With<FullMap>>) {
+    if let Ok(mut visibility) = full_map.single_mut() {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn pan_zoom_map(
+    time: Res<Time>,
+    actions: Res<ButtonInput<Action>>,
+    gamepads: Query<&Gamepad>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    mut map_view: ResMut<MapView>,
+    mut full_map_image: Query<&mut ImageNode, With<FullMapImage>>,
+) {
+    let mut zoom = map_view.zoom;
+    for event in mouse_wheel.read() {
+        zoom *= 1.0 + event.y.signum() * 0.1;
+    }
+    if actions.pressed(Action::ZoomIn) {
+        zoom *= 1.0 + time.delta_secs();
+    }
+    if actions.pressed(Action::ZoomOut) {
+        zoom /= 1.0 + time.delta_secs();
+    }
+    for gamepad in gamepads.iter() {
+        let stick = gamepad.right_stick().y;
+        if stick.abs() > STICK_ZOOM_DEADZONE {
+            zoom *= 1.0 + stick * time.delta_secs();
+        }
+    }
+    map_view.zoom = zoom.clamp(MAP_MIN_ZOOM, MAP_MAX_ZOOM);
+
+    let pressed = |action: Action| actions.pressed(action) as i32 as f32;
+    let pan = Vec2::new(
+        pressed(Action::MoveRight) - pressed(Action::MoveLeft),
+        pressed(Action::DrillDown) - pressed(Action::Thrust),
+    );
+    let rect = view_rect(
+        map_view.center + pan * MAP_PAN_SPEED * time.delta_secs() / map_view.zoom,
+        map_view_size(map_view.zoom),
+    );
+    map_view.center = rect.center();
+    if let Ok(mut image_node) = full_map_image.single_mut() {
+        image_node.rect = Some(rect);
+    }
+}
+
+/// Midpoint of the drills, where the maps are centered
+fn crew_center(player: &Query<&Transform, With<Player>>) -> Option<Vec2> {
+    let count = player.iter().count();
+    (count > 0).then(|| {
+        player
+            .iter()
+            .map(|transform| transform.translation.truncate())
+            .sum::<Vec2>()
+            / count as f32
+    })
+}
+
+fn map_view_size(zoom: f32) -> f32 {
+    GRID_WIDTH as f32 / zoom
+}
+
+/// Square window of the map texture centered on `center`, kept inside the texture bounds
+fn view_rect(center: Vec2, size: f32) -> Rect {
+    let bounds = Vec2::new(GRID_WIDTH as f32, GRID_HEIGHT as f32);
+    let half_size = Vec2::splat(size / 2.0).min(bounds / 2.0);
+    let center = center.clamp(half_size, bounds - half_size);
+    Rect::from_center_half_size(center, half_size)
+}
+
+fn grid_to_pixel(position: (i32, i32)) -> IVec2 {
+    IVec2::new(position.0 + (GRID_WIDTH / 2) as i32, -position.1 - 1)
+}
+
+fn paint_cross(data: &mut [u8], position: (i32, i32), color: [u8; 4]) {
+    let pixel = grid_to_pixel(position);
+    for (dx, dy) in [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)] {
+        write_pixel(data, pixel.x + dx, pixel.y + dy, color);
+    }
+}
+
+fn write_pixel(data: &mut [u8], x: i32, y: i32, color: [u8; 4]) {
+    if x < 0 || y < 0 || x >= GRID_WIDTH as i32 || y >= GRID_HEIGHT as i32 {
+        return;
+    }
+    let index = (y as usize * GRID_WIDTH as usize + x as usize) * 4;
+    data[index..index + 4].copy_from_slice(&color);
+}
+
+fn tile_color(tile_type: &TileType) -> [u8; 4] {
+    match tile_type {
+        TileType::Empty => [40, 40, 55, 255],
+        TileType::Solid => [110, 80, 55, 255],
+        TileType::Sand => [190, 160, 100, 255],
+        TileType::Iron => [170, 110, 90, 255],
+        TileType::Copper => [220, 130, 50, 255],
+        TileType::Gold => [250, 210, 40, 255],
+        TileType::Crystal => [100, 220, 250, 255],
+    }
+}
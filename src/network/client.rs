@@ -0,0 +1,232 @@
+#![allow(clippy::type_complexity)]
+
+use crate::network::protocol::{InputMessage, Message, Snapshot};
+use crate::network::{CLIENT_TIMEOUT_SECS, RECEIVE_BUFFER_SIZE};
+use crate::prelude::*;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::RigidBody;
+use std::collections::VecDeque;
+use std::net::{SocketAddr, UdpSocket};
+
+/// Drills are drawn this far (s) in the past, between the two snapshots around that moment
+const INTERPOLATION_DELAY: f64 = 0.1;
+const HELLO_INTERVAL_SECS: f32 = 1.0;
+
+/// Mirror side of an online game: sends the local actions, shows the host's snapshots
+#[derive(Resource)]
+pub struct NetworkClient {
+    socket: UdpSocket,
+    host: SocketAddr,
+    player: Option<PlayerId>,
+    hello_timer: Timer,
+    input_sequence: u32,
+    // Host run being mirrored, and how many of its destroyed tiles have been applied
+    run: Option<u32>,
+    tiles_applied: u32,
+    last_heard: f64,
+}
+
+impl NetworkClient {
+    pub fn new(socket: UdpSocket, host: SocketAddr) -> Self {
+        let mut hello_timer = Timer::from_seconds(HELLO_INTERVAL_SECS, TimerMode::Repeating);
+        hello_timer.tick(hello_timer.duration());
+        Self {
+            socket,
+            host,
+            player: None,
+            hello_timer,
+            input_sequence: 0,
+            run: None,
+            tiles_applied: 0,
+            last_heard: 0.0,
+        }
+    }
+
+    fn send(&self, message: &Message) {
+        if let Err(error) = self.socket.send_to(&message.encode(), self.host) {
+            warn!("Cannot reach the host {}: {}", self.host, error);
+        }
+    }
+}
+
+/// Message from the host for the local player, shown as a HUD toast
+#[derive(Event)]
+pub struct HostNoticeEvent {
+    pub text: String,
+}
+
+/// Positions received for a mirrored drill, with the local time they arrived at
+#[derive(Component, Default)]
+pub struct NetworkInterpolation {
+    samples: VecDeque<(f64, Vec2)>,
+}
+
+/// Says hello until the host answers, then sends the local actions every frame
+pub fn send_input(
+    time: Res<Time>,
+    mut client: ResMut<NetworkClient>,
+    actions: Res<ButtonInput<Action>>,
+    axes: Res<ActionAxes>,
+) {
+    if client.player.is_none() {
+        if client.hello_timer.tick(time.delta()).just_finished() {
+            info!("Joining {}", client.host);
+            client.send(&Message::Hello);
+        }
+        return;
+    }
+    client.input_sequence += 1;
+    let input = InputMessage::new(client.input_sequence, &actions, &axes, client.tiles_applied);
+    client.send(&Message::Input(input));
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn receive_host_messages(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut client: ResMut<NetworkClient>,
+    game_state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    mut map_seed: ResMut<MapSeed>,
+    mut coop: ResMut<CoopConfig>,
+    mut loading_progress: ResMut<LoadingProgress>,
+    mut world_grid: Option<ResMut<WorldGrid>>,
+    mut notice_events: EventWriter<HostNoticeEvent>,
+    mut drills: Query<
+        (
+            &PlayerId,
+            &mut NetworkInterpolation,
+            &mut Sprite,
+            &mut Fuel,
+            &mut Health,
+            &mut Currency,
+        ),
+        With<Player>,
+    >,
+) {
+    let mut buffer = [0; RECEIVE_BUFFER_SIZE];
+    while let Ok((length, address)) = client.socket.recv_from(&mut buffer) {
+        if address != client.host {
+            continue;
+        }
+        let Some(message) = Message::decode(&buffer[..length]) else {
+            continue;
+        };
+        client.last_heard = time.elapsed_secs_f64();
+        match message {
+            Message::Welcome { player } if client.player.is_none() => {
+                let player = PlayerId(player as usize);
+                info!("Joined the host as {}", player.name());
+                client.player = Some(player);
+            }
+            Message::Refused => {
+                warn!("The host refused to let us in, retrying");
+            }
+            Message::Snapshot(snapshot) if client.run != Some(snapshot.run) => {
+                // The host started a new run: generate the same map and spawn the same crew
+                info!("Host started run {} on seed {}", snapshot.run, snapshot.seed);
+                client.run = Some(snapshot.run);
+                client.tiles_applied = 0;
+                map_seed.0 = snapshot.seed;
+                coop.players = snapshot.players as usize;
+                loading_progress.rendering_map = false;
+                loading_progress.spawning_player = false;
+                loading_progress.spawning_base = false;
+                next_state.set(GameState::Rendering);
+                next_menu_state.set(MenuState::None);
+            }
+            Message::Snapshot(snapshot) if *game_state.get() == GameState::Playing => {
+                if let Some(world_grid) = world_grid.as_deref_mut() {
+                    apply_tiles(&mut commands, &mut client, world_grid, &snapshot);
+                }
+                let now = time.elapsed_secs_f64();
+                for state in snapshot.drills.iter() {
+                    let Some(drill) = drills
+                        .iter_mut()
+                        .find(|(player_id, ..)| player_id.0 == state.player as usize)
+                    else {
+                        continue;
+                    };
+                    let (_, mut interpolation, mut sprite, mut fuel, mut health, mut currency) =
+                        drill;
+                    interpolation.samples.push_back((now, state.position));
+                    sprite.flip_x = state.flip_x;
+                    fuel.current = state.fuel;
+                    health.current = state.health;
+                    currency.amount = state.currency;
+                }
+            }
+            Message::RunOver if *game_state.get() == GameState::Playing => {
+                info!("The host's run is over");
+                next_menu_state.set(MenuState::GameOver);
+                next_state.set(GameState::Menu);
+            }
+            Message::Notice(text) => {
+                info!("Host: {}", text);
+                notice_events.write(HostNoticeEvent { text });
+            }
+            _ => {}
+        }
+    }
+
+    if client.player.is_some()
+        && time.elapsed_secs_f64() - client.last_heard > CLIENT_TIMEOUT_SECS
+    {
+        warn!("Lost the host, joining again");
+        client.player = None;
+    }
+}
+
+/// Removes the tiles the host destroyed, skipping the ones already applied
+fn apply_tiles(
+    commands: &mut Commands,
+    client: &mut NetworkClient,
+    world_grid: &mut WorldGrid,
+    snapshot: &Snapshot,
+) {
+    // A gap means a snapshot got lost, the host resends from the acknowledged tile
+    if snapshot.tiles_from > client.tiles_applied {
+        return;
+    }
+    let skip = (client.tiles_applied - snapshot.tiles_from) as usize;
+    for position in snapshot.tiles.iter().skip(skip) {
+        if let Some(entity) = world_grid.clear_tile(*position) {
+            commands.entity(entity).despawn();
+        }
+        client.tiles_applied += 1;
+    }
+}
+
+/// The host simulates every drill, here they only follow the snapshots
+pub fn make_puppets(mut commands: Commands, new_drills: Query<Entity, Added<Player>>) {
+    for entity in new_drills.iter() {
+        commands.entity(entity).insert((
+            RigidBody::KinematicPositionBased,
+            NetworkInterpolation::default(),
+        ));
+    }
+}
+
+pub fn interpolate_drills(
+    time: Res<Time>,
+    mut drills: Query<(&mut NetworkInterpolation, &mut Transform), With<Player>>,
+) {
+    let render_time = time.elapsed_secs_f64() - INTERPOLATION_DELAY;
+    for (mut interpolation, mut transform) in drills.iter_mut() {
+        let samples = &mut interpolation.samples;
+        // Keep the last sample before the render time and everything after it
+        while samples.len() > 2 && samples[1].0 <= render_time {
+            samples.pop_front();
+        }
+        let position = match (samples.front(), samples.get(1)) {
+            (Some(from), Some(to)) if to.0 > from.0 => {
+                let t = ((render_time - from.0) / (to.0 - from.0)).clamp(0.0, 1.0) as f32;
+                from.1.lerp(to.1, t)
+            }
+            (Some(from), _) => from.1,
+            _ => continue,
+        };
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
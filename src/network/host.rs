@@ -0,0 +1,366 @@
+#![allow(clippy::type_complexity)]
+
+use crate::network::protocol::{
+    InputMessage, MAX_TILES_PER_SNAPSHOT, Message, PlayerSnapshot, Snapshot, action_bit,
+};
+use crate::network::{CLIENT_TIMEOUT_SECS, RECEIVE_BUFFER_SIZE};
+use crate::prelude::*;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::{CollisionEvent, Velocity};
+use std::net::{SocketAddr, UdpSocket};
+
+/// Snapshots sent per second to every client
+const SNAPSHOT_RATE: f32 = 20.0;
+
+/// Authoritative side of an online game: simulates the run and streams it to the clients
+#[derive(Resource)]
+pub struct NetworkHost {
+    socket: UdpSocket,
+    clients: Vec<RemoteClient>,
+    // Every tile destroyed in this run, in order, clients acknowledge a prefix of it
+    tile_log: Vec<(i32, i32)>,
+    run: u32,
+    snapshot_timer: Timer,
+}
+
+struct RemoteClient {
+    address: SocketAddr,
+    player: PlayerId,
+    tiles_acked: u32,
+    last_sequence: u32,
+    last_heard: f64,
+    responding: bool,
+}
+
+impl NetworkHost {
+    pub fn new(socket: UdpSocket) -> Self {
+        Self {
+            socket,
+            clients: Vec::new(),
+            tile_log: Vec::new(),
+            run: 0,
+            snapshot_timer: Timer::from_seconds(1.0 / SNAPSHOT_RATE, TimerMode::Repeating),
+        }
+    }
+
+    pub fn has_clients(&self) -> bool {
+        !self.clients.is_empty()
+    }
+
+    fn send(&self, address: SocketAddr, message: &Message) {
+        if let Err(error) = self.socket.send_to(&message.encode(), address) {
+            warn!("Cannot reach {}: {}", address, error);
+        }
+    }
+}
+
+/// Drill driven by a remote client instead of the local keyboard and gamepads
+#[derive(Component, Default)]
+pub struct RemoteInput {
+    // Actions held in the latest input, and every action seen pressed since the last frame
+    pressed: u32,
+    pressed_since_frame: u32,
+    horizontal: f32,
+    thrust: f32,
+}
+
+/// Welcomes new clients and stores the inputs of the known ones
+pub fn receive_client_messages(
+    time: Res<Time>,
+    mut host: ResMut<NetworkHost>,
+    mut coop: ResMut<CoopConfig>,
+    game_state: Res<State<GameState>>,
+    mut remote_drills: Query<(&PlayerId, &mut RemoteInput), With<Player>>,
+) {
+    let mut buffer = [0; RECEIVE_BUFFER_SIZE];
+    while let Ok((length, address)) = host.socket.recv_from(&mut buffer) {
+        let Some(message) = Message::decode(&buffer[..length]) else {
+            continue;
+        };
+        let known = host.clients.iter().position(|c| c.address == address);
+        match (message, known) {
+            (Message::Hello, Some(index)) => {
+                // The welcome got lost, send it again
+                let player = host.clients[index].player;
+                host.send(address, &Message::Welcome { player: player.0 as u8 });
+            }
+            (Message::Hello, None) => {
+                // Drills are spawned when the run starts, so the crew is closed afterwards
+                if *game_state.get() != GameState::MainMenu
+                    || coop.players >= CoopConfig::MAX_PLAYERS
+                {
+                    info!("Refused {}", address);
+                    host.send(address, &Message::Refused);
+                    continue;
+                }
+                let player = PlayerId(coop.players);
+                coop.players += 1;
+                info!("{} joined from {}", player.name(), address);
+                host.clients.push(RemoteClient {
+                    address,
+                    player,
+                    tiles_acked: 0,
+                    last_sequence: 0,
+                    last_heard: time.elapsed_secs_f64(),
+                    responding: true,
+                });
+                host.send(address, &Message::Welcome { player: player.0 as u8 });
+            }
+            (Message::Input(input), Some(index)) => {
+                let client = &mut host.clients[index];
+                client.last_heard = time.elapsed_secs_f64();
+                client.responding = true;
+                client.tiles_acked = input.tiles_applied;
+                // Datagrams can arrive out of order, older inputs are stale
+                if input.sequence < client.last_sequence {
+                    continue;
+                }
+                client.last_sequence = input.sequence;
+                let player = client.player;
+                if let Some((_, mut remote_input)) =
+                    remote_drills.iter_mut().find(|(id, _)| **id == player)
+                {
+                    apply_input_message(&mut remote_input, &input);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // A silent client lets go of its controls, its drill stays in the run
+    let now = time.elapsed_secs_f64();
+    for client in host.clients.iter_mut() {
+        if client.responding && now - client.last_heard > CLIENT_TIMEOUT_SECS {
+            warn!("{} stopped responding", client.player.name());
+            client.responding = false;
+            if let Some((_, mut remote_input)) =
+                remote_drills.iter_mut().find(|(id, _)| **id == client.player)
+            {
+                *remote_input = RemoteInput::default();
+            }
+        }
+    }
+}
+
+fn apply_input_message(remote_input: &mut RemoteInput, input: &InputMessage) {
+    remote_input.pressed = input.pressed;
+    remote_input.pressed_since_frame |= input.pressed;
+    remote_input.horizontal = input.horizontal;
+    remote_input.thrust = input.thrust;
+}
+
+/// Feeds the received inputs to the remote drills like `update_actions` does for local ones.
+/// A tap shorter than a frame is still seen as a press
+pub fn apply_remote_input(mut remote_drills: Query<(&mut RemoteInput, &mut PlayerActions)>) {
+    for (mut remote_input, mut actions) in remote_drills.iter_mut() {
+        actions.buttons.clear();
+        for action in Action::ALL {
+            let bit = action_bit(action);
+            if remote_input.pressed_since_frame & bit != 0 && !actions.buttons.pressed(action) {
+                actions.buttons.press(action);
+            }
+            if remote_input.pressed & bit == 0 && actions.buttons.pressed(action) {
+                actions.buttons.release(action);
+            }
+        }
+        remote_input.pressed_since_frame = remote_input.pressed;
+        actions.axes.horizontal = remote_input.horizontal;
+        actions.axes.thrust = remote_input.thrust;
+    }
+}
+
+/// Hands the drills of the connected clients over to their inputs
+pub fn tag_remote_drills(
+    mut commands: Commands,
+    host: Res<NetworkHost>,
+    new_drills: Query<(Entity, &PlayerId), Added<Player>>,
+) {
+    for (entity, player_id) in new_drills.iter() {
+        if host.clients.iter().any(|client| client.player == *player_id) {
+            commands.entity(entity).insert(RemoteInput::default());
+        }
+    }
+}
+
+pub fn start_run_log(mut host: ResMut<NetworkHost>) {
+    host.run += 1;
+    host.tile_log.clear();
+}
+
+pub fn record_destroyed_tiles(
+    mut host: ResMut<NetworkHost>,
+    mut tile_destroyed_events: EventReader<TileDestroyedEvent>,
+) {
+    for event in tile_destroyed_events.read() {
+        host.tile_log.push(event.position);
+    }
+}
+
+/// Streams the drills and the tiles each client has not acknowledged yet
+pub fn send_snapshots(
+    time: Res<Time>,
+    mut host: ResMut<NetworkHost>,
+    coop: Res<CoopConfig>,
+    map_seed: Res<MapSeed>,
+    drills: Query<(&PlayerId, &Transform, &Sprite, &Fuel, &Health), With<Player>>,
+    wallets: Query<(&PlayerId, &Currency), With<Player>>,
+) {
+    if !host.snapshot_timer.tick(time.delta()).just_finished() || host.clients.is_empty() {
+        return;
+    }
+    let drills: Vec<PlayerSnapshot> = drills
+        .iter()
+        .map(|(player_id, transform, sprite, fuel, health)| {
+            let wallet = coop.wallet_of(player_id.0);
+            PlayerSnapshot {
+                player: player_id.0 as u8,
+                position: transform.translation.truncate(),
+                flip_x: sprite.flip_x,
+                fuel: fuel.current,
+                health: health.current,
+                currency: wallets
+                    .iter()
+                    .find(|(id, _)| id.0 == wallet)
+                    .map_or(0, |(_, currency)| currency.amount),
+            }
+        })
+        .collect();
+    for client in host.clients.iter() {
+        // A stale acknowledgement from the previous run restarts from the first tile
+        let tiles_from = (client.tiles_acked as usize).min(host.tile_log.len());
+        let tiles_to = (tiles_from + MAX_TILES_PER_SNAPSHOT).min(host.tile_log.len());
+        let snapshot = Snapshot {
+            run: host.run,
+            seed: map_seed.0,
+            players: coop.players as u8,
+            drills: drills.clone(),
+            tiles_from: tiles_from as u32,
+            tiles: host.tile_log[tiles_from..tiles_to].to_vec(),
+        };
+        host.send(client.address, &Message::Snapshot(snapshot));
+    }
+}
+
+pub fn announce_run_over(host: Res<NetworkHost>) {
+    // No acknowledgement for this one, a few copies make up for lost datagrams
+    for client in host.clients.iter() {
+        for _ in 0..3 {
+            host.send(client.address, &Message::RunOver);
+        }
+    }
+}
+
+/// Remote players have no menu on the host: driving into a building uses its services on its own,
+/// the ones to pick from a menu are left to the host
+#[allow(clippy::too_many_arguments)]
+pub fn service_remote_visits(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut remote_drills: Query<
+        (&PlayerId, &mut Inventory, &mut Fuel, &mut Health),
+        With<RemoteInput>,
+    >,
+    mut wallets: Query<(&PlayerId, &mut Currency), With<Player>>,
+    buildings: Query<&Building>,
+    settlement: Res<Settlement>,
+    coop: Res<CoopConfig>,
+    economy: Res<EconomyConfig>,
+    mut market: ResMut<Market>,
+    mut loot_sold_events: EventWriter<LootSoldEvent>,
+    host: Res<NetworkHost>,
+) {
+    for event in collision_events.read() {
+        let CollisionEvent::Started(collider1, collider2, _) = event else {
+            continue;
+        };
+        let (building, drill) = if let Ok(building) = buildings.get(*collider2) {
+            (building, *collider1)
+        } else if let Ok(building) = buildings.get(*collider1) {
+            (building, *collider2)
+        } else {
+            continue;
+        };
+        let Ok((player_id, mut inventory, mut fuel, mut health)) = remote_drills.get_mut(drill)
+        else {
+            continue;
+        };
+        let wallet = coop.wallet_of(player_id.0);
+        let Some((_, mut currency)) = wallets.iter_mut().find(|(id, _)| id.0 == wallet) else {
+            continue;
+        };
+        let building = &settlement.buildings[building.0];
+        info!("{} is serviced at the {}", player_id.name(), building.name);
+        if building.offers(BuildingService::Exchange) {
+            let items = sell_all_inventory(&mut inventory, &mut currency, &mut market);
+            if !items.is_empty() {
+                loot_sold_events.write(LootSoldEvent { items });
+            }
+        }
+        if building.offers(BuildingService::Repair) {
+            repair_hull(&mut health, &mut currency, &economy);
+        }
+        if building.offers(BuildingService::Refuel) {
+            refill_tank(&mut fuel, &mut currency, &economy);
+        }
+        // Picking modules and items needs the base menu, which only the host has
+        if building.offers(BuildingService::Upgrades) || building.offers(BuildingService::Shop) {
+            if let Some(client) = host.clients.iter().find(|c| c.player == *player_id) {
+                let notice = format!("The {} only serves the host", building.name);
+                host.send(client.address, &Message::Notice(notice));
+            }
+        }
+    }
+}
+
+/// Remote players have no rescue menu either: a stranded drill is towed at once if its wallet,
+/// or else its cargo, covers the price, otherwise the run is over for the crew
+#[allow(clippy::too_many_arguments)]
+pub fn rescue_remote_drills(
+    mut remote_drills: Query<
+        (&PlayerId, &mut Inventory, &mut Fuel, &Health, &mut Transform, &mut Velocity),
+        With<RemoteInput>,
+    >,
+    mut wallets: Query<(&PlayerId, &mut Currency), With<Player>>,
+    world_base: Query<&GlobalTransform, With<WorldBase>>,
+    coop: Res<CoopConfig>,
+    economy: Res<EconomyConfig>,
+    market: Res<Market>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    mut menu_player: ResMut<MenuPlayer>,
+) {
+    let Ok(base_transform) = world_base.single() else {
+        return;
+    };
+    for (player_id, mut inventory, mut fuel, health, mut transform, mut velocity) in
+        remote_drills.iter_mut()
+    {
+        if fuel.current > 0.0 || health.current <= 0.0 {
+            continue;
+        }
+        let wallet = coop.wallet_of(player_id.0);
+        let Some((_, mut currency)) = wallets.iter_mut().find(|(id, _)| id.0 == wallet) else {
+            continue;
+        };
+        let price = tow_price(&economy, &transform, &inventory);
+        let paid = if currency.amount >= price {
+            currency.amount -= price;
+            true
+        } else {
+            forfeit_cargo(&mut inventory, &market, price)
+        };
+        if !paid {
+            info!("{} cannot afford the tow", player_id.name());
+            menu_player.0 = *player_id;
+            next_menu_state.set(MenuState::GameOver);
+            next_state.set(GameState::Menu);
+            return;
+        }
+        info!("{} ran dry and is towed for {}c", player_id.name(), price);
+        tow_to_base(
+            &mut transform,
+            &mut velocity,
+            &mut fuel,
+            base_transform.translation().truncate(),
+        );
+    }
+}
@@ -0,0 +1,124 @@
+pub mod client;
+pub mod host;
+pub mod protocol;
+
+pub use client::*;
+pub use host::*;
+
+use crate::controls::update_actions;
+use crate::player::death_detection;
+use crate::prelude::GameState::{Playing, Rendering};
+use crate::prelude::MenuState;
+use bevy::prelude::*;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+/// Port the host listens on when `--host` is given without one
+const DEFAULT_PORT: u16 = 7777;
+/// Seconds without datagrams before the other side is considered gone
+const CLIENT_TIMEOUT_SECS: f64 = 5.0;
+const RECEIVE_BUFFER_SIZE: usize = 2048;
+
+/// Online co-op, picked on the command line:
+/// `--host [port]` runs the authoritative game, `--join <address[:port]>` mirrors it
+pub struct NetworkPlugin;
+
+impl Plugin for NetworkPlugin {
+    fn build(&self, app: &mut App) {
+        let args: Vec<String> = std::env::args().collect();
+        if let Some(port) = arg_value(&args, "--host") {
+            let port = port.and_then(|p| p.parse().ok()).unwrap_or(DEFAULT_PORT);
+            match bind(("0.0.0.0", port)) {
+                Ok(socket) => {
+                    info!("Hosting on port {}", port);
+                    app.insert_resource(NetworkHost::new(socket));
+                }
+                Err(error) => warn!("Cannot host on port {}: {}", port, error),
+            }
+        } else if let Some(Some(address)) = arg_value(&args, "--join") {
+            match resolve(address).and_then(|host| Ok((bind(("0.0.0.0", 0))?, host))) {
+                Ok((socket, host)) => {
+                    info!("Joining {}", host);
+                    app.insert_resource(NetworkClient::new(socket, host));
+                }
+                Err(error) => warn!("Cannot join {}: {}", address, error),
+            }
+        }
+
+        app.add_event::<HostNoticeEvent>();
+        app.add_systems(
+            PreUpdate,
+            (receive_client_messages, apply_remote_input)
+                .chain()
+                .after(update_actions)
+                .run_if(resource_exists::<NetworkHost>),
+        )
+        .add_systems(
+            Update,
+            (
+                tag_remote_drills,
+                service_remote_visits.run_if(in_state(Playing)),
+                record_destroyed_tiles,
+                send_snapshots.run_if(in_state(Playing)),
+            )
+                .chain()
+                .run_if(resource_exists::<NetworkHost>),
+        )
+        .add_systems(
+            Update,
+            rescue_remote_drills
+                .before(death_detection)
+                .run_if(in_state(Playing).and(resource_exists::<NetworkHost>)),
+        )
+        .add_systems(
+            OnEnter(Rendering),
+            start_run_log.run_if(resource_exists::<NetworkHost>),
+        )
+        .add_systems(
+            OnEnter(MenuState::GameOver),
+            announce_run_over.run_if(resource_exists::<NetworkHost>),
+        )
+        .add_systems(
+            PreUpdate,
+            receive_host_messages
+                .after(update_actions)
+                .run_if(resource_exists::<NetworkClient>),
+        )
+        .add_systems(
+            Update,
+            (send_input, make_puppets, interpolate_drills)
+                .run_if(resource_exists::<NetworkClient>),
+        );
+    }
+}
+
+/// Only the host, or an offline game, runs the simulation; clients mirror the host's snapshots
+pub fn simulates_world(client: Option<Res<NetworkClient>>) -> bool {
+    client.is_none()
+}
+
+/// `None` when the flag is missing, `Some(None)` when it has no value
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<Option<&'a str>> {
+    let index = args.iter().position(|arg| arg == flag)?;
+    Some(
+        args.get(index + 1)
+            .map(String::as_str)
+            .filter(|value| !value.starts_with("--")),
+    )
+}
+
+fn bind(address: (&str, u16)) -> std::io::Result<UdpSocket> {
+    let socket = UdpSocket::bind(address)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+fn resolve(address: &str) -> std::io::Result<SocketAddr> {
+    let address = if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, DEFAULT_PORT)
+    };
+    address.to_socket_addrs()?.next().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "no address found")
+    })
+}
@@ -0,0 +1,330 @@
+use crate::controls::{Action, ActionAxes};
+use bevy::math::Vec2;
+use bevy::prelude::ButtonInput;
+
+/// First byte of every datagram, packets from other versions are dropped
+const PROTOCOL_VERSION: u8 = 1;
+/// Destroyed tiles sent in one snapshot, keeps the datagram under the usual MTU
+pub const MAX_TILES_PER_SNAPSHOT: usize = 120;
+
+/// Datagrams exchanged between the host and its clients
+#[derive(Debug, PartialEq)]
+pub enum Message {
+    /// Client asking to join, repeated until it is welcomed
+    Hello,
+    /// Host accepting a client, with the drill it will control
+    Welcome { player: u8 },
+    /// Host turning a client down: the crew is full or a run is in progress
+    Refused,
+    Input(InputMessage),
+    Snapshot(Snapshot),
+    /// The host's run ended, clients show the game over screen
+    RunOver,
+    /// Host telling a client about something only the host can do, shown as a HUD toast
+    Notice(String),
+}
+
+/// Actions held by a client this frame
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct InputMessage {
+    pub sequence: u32,
+    // One bit per entry of `Action::ALL`
+    pub pressed: u32,
+    pub horizontal: f32,
+    pub thrust: f32,
+    // Destroyed tiles applied so far, the host resends everything after them
+    pub tiles_applied: u32,
+}
+
+impl InputMessage {
+    pub fn new(
+        sequence: u32,
+        actions: &ButtonInput<Action>,
+        axes: &ActionAxes,
+        tiles_applied: u32,
+    ) -> Self {
+        Self {
+            sequence,
+            pressed: actions
+                .get_pressed()
+                .fold(0, |mask, action| mask | action_bit(*action)),
+            horizontal: axes.horizontal,
+            thrust: axes.thrust,
+            tiles_applied,
+        }
+    }
+}
+
+/// Bit of the action in `InputMessage::pressed`
+pub fn action_bit(action: Action) -> u32 {
+    Action::ALL
+        .iter()
+        .position(|a| *a == action)
+        .map_or(0, |index| 1 << index)
+}
+
+/// State of the host's run, sent to every client several times per second
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Snapshot {
+    // Counts the host's runs, a new value makes the client start over
+    pub run: u32,
+    pub seed: u64,
+    pub players: u8,
+    pub drills: Vec<PlayerSnapshot>,
+    // Index in the host's log of the first tile below
+    pub tiles_from: u32,
+    pub tiles: Vec<(i32, i32)>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PlayerSnapshot {
+    pub player: u8,
+    pub position: Vec2,
+    pub flip_x: bool,
+    pub fuel: f32,
+    pub health: f32,
+    pub currency: u32,
+}
+
+impl Message {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![PROTOCOL_VERSION];
+        match self {
+            Message::Hello => bytes.push(0),
+            Message::Welcome { player } => {
+                bytes.push(1);
+                bytes.push(*player);
+            }
+            Message::Refused => bytes.push(2),
+            Message::Input(input) => {
+                bytes.push(3);
+                put_u32(&mut bytes, input.sequence);
+                put_u32(&mut bytes, input.pressed);
+                put_f32(&mut bytes, input.horizontal);
+                put_f32(&mut bytes, input.thrust);
+                put_u32(&mut bytes, input.tiles_applied);
+            }
+            Message::Snapshot(snapshot) => {
+                bytes.push(4);
+                put_u32(&mut bytes, snapshot.run);
+                bytes.extend_from_slice(&snapshot.seed.to_le_bytes());
+                bytes.push(snapshot.players);
+                bytes.push(snapshot.drills.len() as u8);
+                for drill in &snapshot.drills {
+                    bytes.push(drill.player);
+                    put_f32(&mut bytes, drill.position.x);
+                    put_f32(&mut bytes, drill.position.y);
+                    bytes.push(drill.flip_x as u8);
+                    put_f32(&mut bytes, drill.fuel);
+                    put_f32(&mut bytes, drill.health);
+                    put_u32(&mut bytes, drill.currency);
+                }
+                put_u32(&mut bytes, snapshot.tiles_from);
+                put_u32(&mut bytes, snapshot.tiles.len() as u32);
+                for (x, y) in &snapshot.tiles {
+                    bytes.extend_from_slice(&x.to_le_bytes());
+                    bytes.extend_from_slice(&y.to_le_bytes());
+                }
+            }
+            Message::RunOver => bytes.push(5),
+            Message::Notice(text) => {
+                bytes.push(6);
+                // Cut on a character boundary to fit the one byte length
+                let mut length = text.len().min(u8::MAX as usize);
+                while !text.is_char_boundary(length) {
+                    length -= 1;
+                }
+                bytes.push(length as u8);
+                bytes.extend_from_slice(&text.as_bytes()[..length]);
+            }
+        }
+        bytes
+    }
+
+    /// `None` for truncated or foreign datagrams
+    pub fn decode(bytes: &[u8]) -> Option<Message> {
+        let mut reader = Reader { bytes, offset: 0 };
+        if reader.u8()? != PROTOCOL_VERSION {
+            return None;
+        }
+        let message = match reader.u8()? {
+            0 => Message::Hello,
+            1 => Message::Welcome { player: reader.u8()? },
+            2 => Message::Refused,
+            3 => Message::Input(InputMessage {
+                sequence: reader.u32()?,
+                pressed: reader.u32()?,
+                horizontal: reader.f32()?,
+                thrust: reader.f32()?,
+                tiles_applied: reader.u32()?,
+            }),
+            4 => {
+                let run = reader.u32()?;
+                let seed = u64::from_le_bytes(reader.take()?);
+                let players = reader.u8()?;
+                let drills = (0..reader.u8()?)
+                    .map(|_| {
+                        Some(PlayerSnapshot {
+                            player: reader.u8()?,
+                            position: Vec2::new(reader.f32()?, reader.f32()?),
+                            flip_x: reader.u8()? != 0,
+                            fuel: reader.f32()?,
+                            health: reader.f32()?,
+                            currency: reader.u32()?,
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                let tiles_from = reader.u32()?;
+                let tiles = (0..reader.u32()?.min(MAX_TILES_PER_SNAPSHOT as u32))
+                    .map(|_| Some((reader.i32()?, reader.i32()?)))
+                    .collect::<Option<Vec<_>>>()?;
+                Message::Snapshot(Snapshot {
+                    run,
+                    seed,
+                    players,
+                    drills,
+                    tiles_from,
+                    tiles,
+                })
+            }
+            5 => Message::RunOver,
+            6 => {
+                let length = reader.u8()? as usize;
+                Message::Notice(String::from_utf8(reader.slice(length)?.to_vec()).ok()?)
+            }
+            _ => return None,
+        };
+        Some(message)
+    }
+}
+
+fn put_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_f32(bytes: &mut Vec<u8>, value: f32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Little-endian cursor over a received datagram
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let chunk = self.bytes.get(self.offset..self.offset + N)?;
+        self.offset += N;
+        chunk.try_into().ok()
+    }
+
+    fn slice(&mut self, length: usize) -> Option<&[u8]> {
+        let chunk = self.bytes.get(self.offset..self.offset + length)?;
+        self.offset += length;
+        Some(chunk)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take()?))
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        Some(i32::from_le_bytes(self.take()?))
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.take()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(message: Message) {
+        assert_eq!(Message::decode(&message.encode()), Some(message));
+    }
+
+    #[test]
+    fn simple_messages_round_trip() {
+        round_trip(Message::Hello);
+        round_trip(Message::Welcome { player: 1 });
+        round_trip(Message::Refused);
+        round_trip(Message::RunOver);
+    }
+
+    #[test]
+    fn notice_round_trips_and_is_cut_to_fit() {
+        round_trip(Message::Notice("The Item Shop only serves the host".to_string()));
+        // 200 two-byte characters: the cut falls on a character boundary
+        let long = "é".repeat(200);
+        let Some(Message::Notice(text)) = Message::decode(&Message::Notice(long).encode()) else {
+            panic!("the notice did not decode");
+        };
+        assert_eq!(text, "é".repeat(127));
+    }
+
+    #[test]
+    fn input_round_trips() {
+        round_trip(Message::Input(InputMessage {
+            sequence: 42,
+            pressed: action_bit(Action::Thrust) | action_bit(Action::UseItem(3)),
+            horizontal: -0.5,
+            thrust: 0.75,
+            tiles_applied: 7,
+        }));
+    }
+
+    #[test]
+    fn snapshot_round_trips() {
+        round_trip(Message::Snapshot(Snapshot {
+            run: 3,
+            seed: u64::MAX - 1,
+            players: 2,
+            drills: vec![
+                PlayerSnapshot {
+                    player: 0,
+                    position: Vec2::new(12.5, -340.0),
+                    flip_x: true,
+                    fuel: 80.0,
+                    health: 55.5,
+                    currency: 1200,
+                },
+                PlayerSnapshot {
+                    player: 1,
+                    ..PlayerSnapshot::default()
+                },
+            ],
+            tiles_from: 10,
+            tiles: vec![(-3, -1), (4, -20)],
+        }));
+    }
+
+    #[test]
+    fn every_action_has_its_own_bit() {
+        let mask = Action::ALL.iter().fold(0u32, |mask, action| {
+            let bit = action_bit(*action);
+            assert_eq!(mask & bit, 0, "{:?} shares a bit", action);
+            mask | bit
+        });
+        assert_eq!(mask.count_ones() as usize, Action::ALL.len());
+    }
+
+    #[test]
+    fn foreign_and_truncated_datagrams_are_dropped() {
+        assert_eq!(Message::decode(&[]), None);
+        assert_eq!(Message::decode(&[PROTOCOL_VERSION + 1, 0]), None);
+        assert_eq!(Message::decode(&[PROTOCOL_VERSION, 99]), None);
+        let snapshot = Message::Snapshot(Snapshot {
+            drills: vec![PlayerSnapshot::default()],
+            ..Snapshot::default()
+        })
+        .encode();
+        assert_eq!(Message::decode(&snapshot[..snapshot.len() - 1]), None);
+    }
+}
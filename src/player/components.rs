@@ -1,9 +1,17 @@
 use crate::animation::DrillAnimation;
+use crate::controls::PlayerActions;
+use crate::player::damage::{Invulnerability, Resistances};
+use crate::player::equipment::Equipment;
+use crate::player::status::StatusEffects;
 use bevy::prelude::*;
 use std::collections::HashSet;
 
+pub const MAX_DRILL_TIER: u8 = 3;
+
 #[derive(Component)]
 #[require(
+    PlayerId,
+    PlayerActions,
     Inventory,
     Health,
     Fuel,
@@ -12,29 +20,71 @@ use std::collections::HashSet;
     PlayerAttributes,
     Currency,
     DrillAnimation,
-    PlayerDirection
+    PlayerDirection,
+    Scanner,
+    RecallDevice,
+    DrillHeat,
+    ToolBelt,
+    ToolState,
+    Hotbar,
+    Equipment,
+    StatusEffects,
+    Resistances,
+    Invulnerability
 )]
 pub struct Player;
 
+/// Index of the local player driving the drill, 0 for the first one
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct PlayerId(pub usize);
+
+impl PlayerId {
+    pub fn name(&self) -> String {
+        format!("Player {}", self.0 + 1)
+    }
+
+    /// Tint of the drill and of its HUD, the first player keeps the original paint
+    pub fn color(&self) -> Color {
+        match self.0 {
+            0 => Color::WHITE,
+            _ => Color::srgb(0.55, 0.8, 1.0),
+        }
+    }
+}
+
 #[derive(Component, PartialEq, Debug, Clone, Copy)]
 pub struct PlayerAttributes {
     pub drill_power: f32,
-    pub damage_factor: f32,
-    pub armor_resistance: f32,
-    pub ground_speed_factor: f32,
-    pub flying_speed_factor: f32,
+    // Drill head tier, tiles with a higher minimum tier cannot be broken
+    pub drill_tier: u8,
+    // Engine acceleration (px/s²) pushing the drill sideways
+    pub acceleration: f32,
+    // Engine acceleration (px/s²) pushing the drill upwards, it has to beat gravity to climb
+    pub thrust: f32,
+    // Speeds (px/s) above which the engine stops pushing
+    pub max_speed: f32,
+    pub max_climb_speed: f32,
+    // Linear damping while moving or airborne, and while idle on the ground (brakes)
+    pub drag: f32,
+    pub brake_damping: f32,
     pub fuel_efficiency: f32,
+    // Mass of the empty drill, the engine is sized for it
+    pub hull_mass: f32,
 }
 
 impl Default for PlayerAttributes {
     fn default() -> Self {
         Self {
             drill_power: 1.0,
-            damage_factor: 0.05,
-            armor_resistance: 0.0,
-            ground_speed_factor: 200.0,
-            flying_speed_factor: 200.0,
+            drill_tier: 1,
+            acceleration: 1500.0,
+            thrust: 2200.0,
+            max_speed: 200.0,
+            max_climb_speed: 200.0,
+            drag: 0.5,
+            brake_damping: 10.0,
             fuel_efficiency: 0.3,
+            hull_mass: 150.0,
         }
     }
 }
@@ -51,16 +101,87 @@ impl Default for PlayerDirection {
     }
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct DrillDirections {
+    pub up: bool,
+    pub diagonal: bool,
+}
+
+/// Which directions each drill head tier can drill, and how costly the unusual ones are.
+/// Sideways and downwards drilling are always allowed.
+#[derive(Resource, Debug)]
+pub struct DrillRules {
+    // Indexed by drill head tier, starting from tier 1
+    pub directions_per_tier: Vec<DrillDirections>,
+    pub up_speed_factor: f32,
+    pub up_fuel_factor: f32,
+    pub diagonal_speed_factor: f32,
+    pub diagonal_fuel_factor: f32,
+}
+
+impl Default for DrillRules {
+    fn default() -> Self {
+        Self {
+            directions_per_tier: vec![
+                DrillDirections {
+                    up: false,
+                    diagonal: false,
+                },
+                DrillDirections {
+                    up: true,
+                    diagonal: false,
+                },
+                DrillDirections {
+                    up: true,
+                    diagonal: true,
+                },
+            ],
+            up_speed_factor: 0.5,
+            up_fuel_factor: 2.0,
+            diagonal_speed_factor: 0.8,
+            diagonal_fuel_factor: 1.25,
+        }
+    }
+}
+
+impl DrillRules {
+    pub fn directions(&self, drill_tier: u8) -> DrillDirections {
+        let index = (drill_tier.max(1) as usize - 1).min(self.directions_per_tier.len() - 1);
+        self.directions_per_tier[index]
+    }
+
+    /// Drilling speed and fuel consumption multipliers for the given drilling direction
+    pub fn factors(&self, (dx, dy): (i32, i32)) -> (f32, f32) {
+        let (mut speed_factor, mut fuel_factor) = (1.0, 1.0);
+        if dx != 0 && dy != 0 {
+            speed_factor *= self.diagonal_speed_factor;
+            fuel_factor *= self.diagonal_fuel_factor;
+        }
+        if dy > 0 {
+            speed_factor *= self.up_speed_factor;
+            fuel_factor *= self.up_fuel_factor;
+        }
+        (speed_factor, fuel_factor)
+    }
+}
+
+/// Sent while the player pushes the drill against a tile too hard for its drill head.
 #[derive(Event)]
-pub struct PlayerImpactEvent {
-    pub impact_speed: f32,
-    pub damage: f32,
+pub struct DrillBlockedEvent {
+    pub contact_point: Vec2,
+    pub required_tier: u8,
+}
+
+/// Sent when the drill touches down on a tile after flying or falling.
+#[derive(Event)]
+pub struct LandingEvent {
+    pub player: Entity,
+    pub speed: f32,
 }
 
 #[derive(Component)]
 pub struct Health {
     pub current: f32,
-    #[allow(dead_code)]
     pub max: f32,
 }
 
@@ -88,6 +209,45 @@ impl Default for Fuel {
     }
 }
 
+#[derive(Component, Debug)]
+pub struct DrillHeat {
+    pub current: f32,
+    pub max: f32,
+    // Above this the drill slows down, once stalled it restarts only below it
+    pub overheat_threshold: f32,
+    pub heating_rate: f32,
+    pub cooling_rate: f32,
+    // Health lost when the drill stalls at maximum heat
+    pub stall_damage: f32,
+    pub stalled: bool,
+}
+
+impl Default for DrillHeat {
+    fn default() -> Self {
+        Self {
+            current: 0.0,
+            max: 100.0,
+            overheat_threshold: 70.0,
+            heating_rate: 12.0,
+            cooling_rate: 8.0,
+            stall_damage: 10.0,
+            stalled: false,
+        }
+    }
+}
+
+impl DrillHeat {
+    /// Drilling speed multiplier, dropping from 1.0 at the threshold to 0.3 at maximum heat
+    pub fn efficiency(&self) -> f32 {
+        if self.current <= self.overheat_threshold {
+            return 1.0;
+        }
+        let overheat =
+            (self.current - self.overheat_threshold) / (self.max - self.overheat_threshold);
+        1.0 - overheat.clamp(0.0, 1.0) * 0.7
+    }
+}
+
 #[derive(Component, PartialEq, Eq, Debug, Clone, Copy, Default)]
 pub enum DrillState {
     #[default]
@@ -97,6 +257,155 @@ pub enum DrillState {
     Falling,
 }
 
+#[derive(Component, Debug)]
+pub struct Scanner {
+    pub range: i32,
+    pub fuel_cost: f32,
+    pub highlight_duration: f32,
+    pub cooldown: Timer,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        let mut cooldown = Timer::from_seconds(8.0, TimerMode::Once);
+        // The scanner is ready to be used as soon as the run starts
+        cooldown.tick(cooldown.duration());
+        Self {
+            range: 6,
+            fuel_cost: 5.0,
+            highlight_duration: 4.0,
+            cooldown,
+        }
+    }
+}
+
+/// Bought at the base, brings the drill home after channeling for a while
+#[derive(Component, Debug)]
+pub struct RecallDevice {
+    pub owned: bool,
+    // Running while the device charges up, moving or taking damage cancels it
+    pub channel: Option<Timer>,
+    pub channel_secs: f32,
+    pub cooldown: Timer,
+    // Fuel burned by a recall, growing with the depth it starts from
+    pub fuel_cost: f32,
+    pub fuel_cost_per_depth: f32,
+}
+
+impl Default for RecallDevice {
+    fn default() -> Self {
+        let mut cooldown = Timer::from_seconds(60.0, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+        Self {
+            owned: false,
+            channel: None,
+            channel_secs: 3.0,
+            cooldown,
+            fuel_cost: 5.0,
+            fuel_cost_per_depth: 0.2,
+        }
+    }
+}
+
+impl RecallDevice {
+    pub fn fuel_cost_at(&self, depth: i32) -> f32 {
+        self.fuel_cost + depth.max(0) as f32 * self.fuel_cost_per_depth
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Tool {
+    #[default]
+    Drill,
+    Dynamite,
+    Laser,
+    Claw,
+    Grapple,
+}
+
+impl Tool {
+    pub fn next(self) -> Self {
+        match self {
+            Tool::Drill => Tool::Dynamite,
+            Tool::Dynamite => Tool::Laser,
+            Tool::Laser => Tool::Claw,
+            Tool::Claw => Tool::Grapple,
+            Tool::Grapple => Tool::Drill,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tool::Drill => "Drill",
+            Tool::Dynamite => "Dynamite",
+            Tool::Laser => "Laser",
+            Tool::Claw => "Claw",
+            Tool::Grapple => "Grappling hook",
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct ToolBelt {
+    pub selected: Tool,
+    pub dynamite: u32,
+    pub max_dynamite: u32,
+    // Blast radius in tiles and damage dealt at its center
+    pub dynamite_radius: i32,
+    pub dynamite_damage: f32,
+    pub laser_range: i32,
+    pub laser_power: f32,
+    pub laser_fuel_rate: f32,
+    // Where the laser beam ends while firing
+    pub laser_hit: Option<Vec2>,
+    pub claw_reach: f32,
+    // Longest rope the grappling hook can fire, and how fast it is reeled in or out (px, px/s)
+    pub grapple_range: f32,
+    pub grapple_reel_speed: f32,
+    pub grapple: Option<GrappleAnchor>,
+    // Time spent in the placing/grabbing states before going back to idle
+    pub action: Timer,
+}
+
+impl Default for ToolBelt {
+    fn default() -> Self {
+        Self {
+            selected: Tool::default(),
+            dynamite: 3,
+            max_dynamite: 5,
+            dynamite_radius: 2,
+            dynamite_damage: 40.0,
+            laser_range: 6,
+            laser_power: 0.3,
+            laser_fuel_rate: 8.0,
+            laser_hit: None,
+            claw_reach: 48.0,
+            grapple_range: 256.0,
+            grapple_reel_speed: 120.0,
+            grapple: None,
+            action: Timer::from_seconds(0.3, TimerMode::Once),
+        }
+    }
+}
+
+/// Tile the grappling hook is stuck in, the drill hangs from `point` on a rope of `length`
+#[derive(Debug, Clone, Copy)]
+pub struct GrappleAnchor {
+    pub tile: Entity,
+    pub point: Vec2,
+    pub length: f32,
+}
+
+#[derive(Component, PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum ToolState {
+    #[default]
+    Idle,
+    Placing,
+    Lasering,
+    Grabbing,
+    Grappling,
+}
+
 #[derive(Component, Clone, PartialEq, Debug)]
 pub struct FieldOfView {
     pub visible_tiles: HashSet<(i32, i32)>,
@@ -114,12 +423,87 @@ impl Default for FieldOfView {
     }
 }
 
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Clone)]
 pub struct Item {
     pub id: String,
     pub name: String,
     pub quantity: usize,
     pub value: u32,
+    // Mass of a single unit
+    pub mass: f32,
+    // Set for usable items, which are kept when the cargo is sold
+    pub consumable: Option<Consumable>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Consumable {
+    RepairKit,
+    FuelCanister,
+    Teleporter,
+    Shield,
+    Overclock,
+}
+
+impl Consumable {
+    pub const ALL: [Consumable; 5] = [
+        Consumable::RepairKit,
+        Consumable::FuelCanister,
+        Consumable::Teleporter,
+        Consumable::Shield,
+        Consumable::Overclock,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Consumable::RepairKit => "repair_kit",
+            Consumable::FuelCanister => "fuel_canister",
+            Consumable::Teleporter => "teleporter",
+            Consumable::Shield => "shield",
+            Consumable::Overclock => "overclock",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Consumable::RepairKit => "Repair Kit",
+            Consumable::FuelCanister => "Fuel Canister",
+            Consumable::Teleporter => "Teleporter",
+            Consumable::Shield => "Shield",
+            Consumable::Overclock => "Overclock Chip",
+        }
+    }
+
+    pub fn to_item(self) -> Item {
+        let mass = match self {
+            Consumable::RepairKit => 5.0,
+            Consumable::FuelCanister => 10.0,
+            Consumable::Teleporter => 3.0,
+            Consumable::Shield => 4.0,
+            Consumable::Overclock => 1.0,
+        };
+        Item {
+            id: self.id().to_string(),
+            name: self.name().to_string(),
+            quantity: 1,
+            value: 0,
+            mass,
+            consumable: Some(self),
+        }
+    }
+}
+
+/// Quick-use slots, slot `n` is triggered by the `n + 1` number key
+#[derive(Component, Debug)]
+pub struct Hotbar {
+    pub slots: [Option<Consumable>; 5],
+}
+
+impl Default for Hotbar {
+    fn default() -> Self {
+        Self {
+            slots: Consumable::ALL.map(Some),
+        }
+    }
 }
 
 #[derive(Component)]
@@ -139,7 +523,7 @@ impl Default for Inventory {
 
 impl Inventory {
     pub fn add_item(&mut self, new_item: Item) {
-        if self.size() + new_item.quantity <= self.capacity {
+        if self.has_room_for(new_item.quantity) {
             if let Some(existing) = self.items.iter_mut().find(|i| i.id == new_item.id) {
                 existing.quantity += new_item.quantity;
             } else {
@@ -154,12 +538,68 @@ impl Inventory {
         self.items.iter().map(|i| i.quantity).sum()
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.items.is_empty()
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+    }
+
+    pub fn has_room_for(&self, quantity: usize) -> bool {
+        self.size() + quantity <= self.capacity
+    }
+
+    pub fn total_mass(&self) -> f32 {
+        self.items.iter().map(|i| i.mass * i.quantity as f32).sum()
+    }
+
+    /// How much heavier than the empty hull the drill is with the current cargo
+    pub fn load_factor(&self, hull_mass: f32) -> f32 {
+        1.0 + self.total_mass() / hull_mass
+    }
+
+    pub fn count(&self, id: &str) -> usize {
+        self.items
+            .iter()
+            .find(|i| i.id == id)
+            .map_or(0, |i| i.quantity)
+    }
+
+    /// Removes a single unit of the item, returns false if there is none
+    pub fn remove_one(&mut self, id: &str) -> bool {
+        let Some(index) = self.items.iter().position(|i| i.id == id) else {
+            return false;
+        };
+        self.items[index].quantity -= 1;
+        if self.items[index].quantity == 0 {
+            self.items.remove(index);
+        }
+        true
+    }
+
+    /// Takes out up to `quantity` units of the item as a stack of their own
+    pub fn take(&mut self, id: &str, quantity: usize) -> Option<Item> {
+        let index = self.items.iter().position(|i| i.id == id)?;
+        let quantity = quantity.min(self.items[index].quantity);
+        if quantity == self.items[index].quantity {
+            return Some(self.items.remove(index));
+        }
+        self.items[index].quantity -= quantity;
+        Some(Item {
+            quantity,
+            ..self.items[index].clone()
+        })
     }
 
-    pub fn clear(&mut self) {
-        self.items.clear();
+    /// Takes out everything that is not a consumable
+    pub fn take_loot(&mut self) -> Vec<Item> {
+        let (loot, consumables) = self
+            .items
+            .drain(..)
+            .partition(|i| i.consumable.is_none());
+        self.items = consumables;
+        loot
     }
 
     pub fn print_items(&self) -> String {
@@ -0,0 +1,106 @@
+#![allow(clippy::type_complexity)]
+
+use crate::controls::{Action, PlayerActions};
+use crate::player::components::*;
+use crate::player::status::{ApplyStatusEvent, StatusKind};
+use crate::player::tools::release_grapple;
+use crate::world_base::WorldBase;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Velocity;
+
+const REPAIR_KIT_HEALTH: f32 = 40.0;
+const FUEL_CANISTER_FUEL: f32 = 50.0;
+const SHIELD_DURATION_SECS: f32 = 6.0;
+const OVERCLOCK_DURATION_SECS: f32 = 10.0;
+
+pub fn use_consumable(
+    mut commands: Commands,
+    mut player: Query<
+        (
+            Entity,
+            &PlayerActions,
+            &Hotbar,
+            &mut Inventory,
+            &mut Health,
+            &mut Fuel,
+            &mut Transform,
+            &mut Velocity,
+            &mut ToolBelt,
+            &mut ToolState,
+        ),
+        With<Player>,
+    >,
+    world_base: Query<&GlobalTransform, With<WorldBase>>,
+    mut status_events: EventWriter<ApplyStatusEvent>,
+) {
+    for (
+        entity,
+        actions,
+        hotbar,
+        mut inventory,
+        mut health,
+        mut fuel,
+        mut transform,
+        mut velocity,
+        mut tool_belt,
+        mut tool_state,
+    ) in player.iter_mut()
+    {
+        let Some(slot) = (0..hotbar.slots.len())
+            .find(|slot| actions.buttons.just_pressed(Action::UseItem(*slot)))
+        else {
+            continue;
+        };
+        let Some(consumable) = hotbar.slots[slot] else {
+            continue;
+        };
+        // Without a base to land on the teleporter would be wasted
+        let base_transform = world_base.single().ok();
+        if matches!(consumable, Consumable::Teleporter) && base_transform.is_none() {
+            info!("No base to teleport to");
+            continue;
+        }
+        if !inventory.remove_one(consumable.id()) {
+            info!("No {} left", consumable.name());
+            continue;
+        }
+        match consumable {
+            Consumable::RepairKit => {
+                health.current = (health.current + REPAIR_KIT_HEALTH).min(health.max);
+                info!("Repaired hull, health={:.1}", health.current);
+            }
+            Consumable::FuelCanister => {
+                fuel.current = (fuel.current + FUEL_CANISTER_FUEL).min(fuel.max);
+                info!("Refueled, fuel={:.1}", fuel.current);
+            }
+            Consumable::Teleporter => {
+                if let Some(base_transform) = base_transform {
+                    // The rope would drag the drill straight back down
+                    if tool_belt.grapple.is_some() {
+                        release_grapple(&mut commands, entity, &mut tool_belt, &mut tool_state);
+                    }
+                    // Landing on the base entrance opens the base menu
+                    let base_position = base_transform.translation().truncate();
+                    transform.translation = base_position.extend(transform.translation.z);
+                    *velocity = Velocity::zero();
+                    info!("Teleported to the base");
+                }
+            }
+            Consumable::Shield => {
+                status_events.write(ApplyStatusEvent {
+                    player: entity,
+                    kind: StatusKind::Shielded,
+                    duration: SHIELD_DURATION_SECS,
+                });
+            }
+            Consumable::Overclock => {
+                status_events.write(ApplyStatusEvent {
+                    player: entity,
+                    kind: StatusKind::Overclocked,
+                    duration: OVERCLOCK_DURATION_SECS,
+                });
+            }
+        }
+    }
+}
+
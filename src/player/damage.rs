@@ -0,0 +1,190 @@
+use crate::player::components::*;
+use crate::player::status::{ApplyStatusEvent, StatusEffects, StatusKind};
+use bevy::prelude::*;
+
+/// Hits at least this strong crack the fuel tank
+const FUEL_LEAK_DAMAGE: f32 = 15.0;
+const FUEL_LEAK_SECS: f32 = 8.0;
+/// Highest share of damage a resistance can absorb
+const MAX_RESISTANCE: f32 = 0.9;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DamageType {
+    Impact,
+    Heat,
+    Explosion,
+}
+
+impl DamageType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DamageType::Impact => "impact",
+            DamageType::Heat => "heat",
+            DamageType::Explosion => "explosion",
+        }
+    }
+
+    /// Single blows, as opposed to damage dealt over time: only these are blocked by the
+    /// shield and start the invulnerability frames
+    pub fn is_hit(&self) -> bool {
+        matches!(self, DamageType::Impact | DamageType::Explosion)
+    }
+}
+
+#[derive(Event)]
+pub struct DamageEvent {
+    pub player: Entity,
+    pub damage_type: DamageType,
+    pub source: &'static str,
+    pub amount: f32,
+}
+
+/// Share of each damage type absorbed by the hull, from 0 to 1
+#[derive(Component, Clone, Copy, Default, Debug, PartialEq)]
+pub struct Resistances {
+    pub impact: f32,
+    pub heat: f32,
+    pub explosion: f32,
+}
+
+impl Resistances {
+    pub fn get(&self, damage_type: DamageType) -> f32 {
+        match damage_type {
+            DamageType::Impact => self.impact,
+            DamageType::Heat => self.heat,
+            DamageType::Explosion => self.explosion,
+        }
+    }
+
+    pub fn combine(self, other: Resistances) -> Resistances {
+        Resistances {
+            impact: self.impact + other.impact,
+            heat: self.heat + other.heat,
+            explosion: self.explosion + other.explosion,
+        }
+    }
+
+    pub fn describe(&self) -> Vec<String> {
+        [DamageType::Impact, DamageType::Heat, DamageType::Explosion]
+        .into_iter()
+        .filter(|damage_type| self.get(*damage_type) != 0.0)
+        .map(|damage_type| {
+            format!(
+                "{:+.0}% {}",
+                self.get(damage_type) * 100.0,
+                damage_type.name()
+            )
+        })
+        .collect()
+    }
+}
+
+/// Grace period after a hit during which further hits are ignored
+#[derive(Component)]
+pub struct Invulnerability {
+    pub timer: Timer,
+}
+
+impl Default for Invulnerability {
+    fn default() -> Self {
+        let mut timer = Timer::from_seconds(0.6, TimerMode::Once);
+        timer.tick(timer.duration());
+        Self { timer }
+    }
+}
+
+pub struct DamageRecord {
+    pub damage_type: DamageType,
+    pub source: &'static str,
+    pub amount: f32,
+}
+
+/// Damage taken during the current run, shown on the game over screen
+#[derive(Resource, Default)]
+pub struct DamageLog {
+    pub records: Vec<DamageRecord>,
+}
+
+impl DamageLog {
+    /// Consecutive damage from the same source, like a fire, is merged into one record
+    pub fn record(&mut self, damage_type: DamageType, source: &'static str, amount: f32) {
+        if let Some(last) = self.records.last_mut() {
+            if last.damage_type == damage_type && last.source == source {
+                last.amount += amount;
+                return;
+            }
+        }
+        self.records.push(DamageRecord {
+            damage_type,
+            source,
+            amount,
+        });
+    }
+}
+
+pub fn apply_damage(
+    time: Res<Time>,
+    mut events: EventReader<DamageEvent>,
+    mut player: Query<
+        (
+            &mut Health,
+            &Resistances,
+            &StatusEffects,
+            &mut Invulnerability,
+        ),
+        With<Player>,
+    >,
+    mut damage_log: ResMut<DamageLog>,
+    mut status_events: EventWriter<ApplyStatusEvent>,
+) {
+    for (_, _, _, mut invulnerability) in player.iter_mut() {
+        invulnerability.timer.tick(time.delta());
+    }
+    for event in events.read() {
+        let Ok((mut health, resistances, status_effects, mut invulnerability)) =
+            player.get_mut(event.player)
+        else {
+            continue;
+        };
+        if event.damage_type.is_hit() {
+            if status_effects.has(StatusKind::Shielded) {
+                info!(
+                    "{:.1} {} damage from {} absorbed by the shield",
+                    event.amount,
+                    event.damage_type.name(),
+                    event.source
+                );
+                continue;
+            }
+            if !invulnerability.timer.finished() {
+                continue;
+            }
+            invulnerability.timer.reset();
+        }
+        let resistance = resistances.get(event.damage_type).min(MAX_RESISTANCE);
+        let actual_damage = event.amount * (1.0 - resistance);
+        health.current -= actual_damage;
+        damage_log.record(event.damage_type, event.source, actual_damage);
+        if event.damage_type.is_hit() {
+            info!(
+                "Hit by {}: {:.1} {} damage (reduced to {:.1}), health={:.1}",
+                event.source,
+                event.amount,
+                event.damage_type.name(),
+                actual_damage,
+                health.current
+            );
+            if actual_damage >= FUEL_LEAK_DAMAGE {
+                status_events.write(ApplyStatusEvent {
+                    player: event.player,
+                    kind: StatusKind::LeakingFuel,
+                    duration: FUEL_LEAK_SECS,
+                });
+            }
+        }
+    }
+}
+
+pub fn reset_damage_log(mut damage_log: ResMut<DamageLog>) {
+    damage_log.records.clear();
+}
@@ -1,58 +1,150 @@
-use crate::map::{Tile, TileDestroyedEvent, WorldGrid, world_to_grid_position};
-use crate::menu::MenuState;
-use crate::prelude::MenuState::GameOver;
+#![allow(clippy::type_complexity)]
+
+use crate::controls::{Action, PlayerActions};
+use crate::map::{
+    GRID_HEIGHT, TILE_SIZE, Tile, TileDestroyedEvent, TileType, WorldGrid,
+    grid_to_world_position, world_to_grid_position,
+};
+use crate::menu::{MenuPlayer, MenuState};
+use crate::prelude::MenuState::{GameOver, Rescue};
 use crate::player::components::*;
-use crate::prelude::GameState;
+use crate::player::damage::{DamageEvent, DamageType};
+use crate::player::status::{ApplyStatusEvent, StatusKind};
+use crate::player::tools::drop_loose_item;
+use crate::prelude::{GameAssets, GameState, RemoteInput};
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::{CollisionEvent, Velocity};
 
+/// Extra heating for each unit of hardness of the drilled tile
+const HARDNESS_HEATING: f32 = 5.0;
+/// A stalled drill sets the machine on fire for this long
+const STALL_BURN_SECS: f32 = 4.0;
+/// Damage dealt for each px/s of landing speed above the safe threshold
+const IMPACT_DAMAGE_FACTOR: f32 = 0.05;
+const SAFE_LANDING_SPEED: f32 = 300.0;
+
+#[allow(clippy::too_many_arguments)]
 pub fn drill(
     time: Res<Time<Fixed>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    drill_rules: Res<DrillRules>,
     mut player: Query<
         (
+            Entity,
+            &PlayerActions,
             &Transform,
             &mut DrillState,
             &PlayerAttributes,
             &mut Fuel,
+            &Inventory,
+            &mut DrillHeat,
+            &ToolBelt,
         ),
         With<Player>,
     >,
     world_grid: Res<WorldGrid>,
     mut query_tile: Query<(&mut Tile, &Transform), With<Tile>>,
     mut tile_destroyed_events: EventWriter<TileDestroyedEvent>,
+    mut drill_blocked_events: EventWriter<DrillBlockedEvent>,
 ) {
-    if let Ok((transform, mut drill_state, attributes, mut fuel)) = player.single_mut() {
+    for (
+        entity,
+        actions,
+        transform,
+        mut drill_state,
+        attributes,
+        mut fuel,
+        inventory,
+        mut heat,
+        tool_belt,
+    ) in player.iter_mut()
+    {
+        if tool_belt.selected != Tool::Drill {
+            if *drill_state == DrillState::Drilling {
+                *drill_state = DrillState::Idle;
+            }
+            continue;
+        }
         let position = transform.translation.truncate();
         let current_position = world_to_grid_position(position);
 
-        let mut direction = keyboard_input.get_pressed().find_map(|key| match key {
-            KeyCode::ArrowLeft => Some((-1, 0)),
-            KeyCode::ArrowRight => Some((1, 0)),
-            KeyCode::ArrowDown => Some((0, -1)),
-            _ => None,
-        });
-        if *drill_state == DrillState::Drilling && direction == None {
+        let pressed = |action: Action| actions.buttons.pressed(action) as i32;
+        let horizontal = pressed(Action::MoveRight) - pressed(Action::MoveLeft);
+        let vertical = pressed(Action::Thrust) - pressed(Action::DrillDown);
+        let allowed = drill_rules.directions(attributes.drill_tier);
+
+        let mut direction = match (horizontal, vertical) {
+            (0, 0) => None,
+            (dx, 0) => Some((dx, 0)),
+            (0, -1) => Some((0, -1)),
+            (0, _) => allowed.up.then_some((0, 1)),
+            (dx, dy) if allowed.diagonal && (dy < 0 || allowed.up) => Some((dx, dy)),
+            (_, -1) => Some((0, -1)),
+            (dx, _) => Some((dx, 0)),
+        };
+        if *drill_state == DrillState::Drilling && direction.is_none() {
             *drill_state = DrillState::Idle;
         }
-        if *drill_state != DrillState::Idle && *drill_state != DrillState::Drilling {
+        // Ceilings can only be drilled while flying against them, everything else from the ground
+        let can_drill = match direction {
+            Some((_, 1)) => {
+                *drill_state == DrillState::Flying || *drill_state == DrillState::Drilling
+            }
+            _ => *drill_state == DrillState::Idle || *drill_state == DrillState::Drilling,
+        };
+        if !can_drill {
             direction = None;
         }
         if let Some((dx, dy)) = direction {
             let target_index = (current_position.0 + dx, current_position.1 + dy);
 
-            if let Some(&entity) = world_grid.grid.get(&target_index) {
-                if let Ok((mut tile, _)) = query_tile.get_mut(entity) {
+            if let Some(&tile_entity) = world_grid.grid.get(&target_index) {
+                if let Ok((mut tile, _)) = query_tile.get_mut(tile_entity) {
+                    // Open air is flown through, not drilled
+                    if tile.tile_type == TileType::Empty {
+                        if *drill_state == DrillState::Drilling {
+                            *drill_state = DrillState::Idle;
+                        }
+                        continue;
+                    }
+                    if heat.stalled {
+                        *drill_state = DrillState::Idle;
+                        continue;
+                    }
+                    if tile.drilling.min_tier > attributes.drill_tier {
+                        *drill_state = DrillState::Idle;
+                        drill_blocked_events.write(DrillBlockedEvent {
+                            contact_point: position
+                                + Vec2::new(dx as f32, dy as f32) * TILE_SIZE / 2.0,
+                            required_tier: tile.drilling.min_tier,
+                        });
+                        continue;
+                    }
                     *drill_state = DrillState::Drilling;
 
-                    tile.drilling.integrity -=
-                        attributes.drill_power * time.delta_secs() * (1.0 - tile.drilling.hardness);
-                    fuel.current -= (1.0 / attributes.fuel_efficiency) * time.delta_secs();
+                    let (speed_factor, fuel_factor) = drill_rules.factors((dx, dy));
+                    tile.drilling.integrity -= attributes.drill_power
+                        * speed_factor
+                        * heat.efficiency()
+                        * time.delta_secs()
+                        * (1.0 - tile.drilling.hardness);
+                    // Harder rock and deeper, hotter strata heat the drill faster
+                    let depth_factor = 1.0 + (-target_index.1).max(0) as f32 / GRID_HEIGHT as f32;
+                    heat.current = (heat.current
+                        + heat.heating_rate
+                            * (1.0 + tile.drilling.hardness * HARDNESS_HEATING)
+                            * depth_factor
+                            * time.delta_secs())
+                    .min(heat.max);
+                    fuel.current -= inventory.load_factor(attributes.hull_mass)
+                        * fuel_factor
+                        * (1.0 / attributes.fuel_efficiency)
+                        * time.delta_secs();
                     if tile.drilling.integrity <= 0.0 {
                         tile_destroyed_events.write(TileDestroyedEvent {
                             tile_type: tile.tile_type,
                             position: target_index,
-                            entity,
+                            entity: tile_entity,
+                            destroyed_by: Some(entity),
                         });
                         *drill_state = DrillState::Idle;
                     }
@@ -67,14 +159,63 @@ pub fn drill(
     }
 }
 
+pub fn update_drill_heat(
+    time: Res<Time<Fixed>>,
+    mut player: Query<(Entity, &DrillState, &mut DrillHeat), With<Player>>,
+    mut status_events: EventWriter<ApplyStatusEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    for (entity, drill_state, mut heat) in player.iter_mut() {
+        if *drill_state != DrillState::Drilling {
+            heat.current = (heat.current - heat.cooling_rate * time.delta_secs()).max(0.0);
+        }
+        if !heat.stalled && heat.current >= heat.max {
+            heat.stalled = true;
+            damage_events.write(DamageEvent {
+                player: entity,
+                damage_type: DamageType::Heat,
+                source: "overheated drill",
+                amount: heat.stall_damage,
+            });
+            status_events.write(ApplyStatusEvent {
+                player: entity,
+                kind: StatusKind::Burning,
+                duration: STALL_BURN_SECS,
+            });
+            info!("Drill overheated and stalled");
+        } else if heat.stalled && heat.current < heat.overheat_threshold {
+            heat.stalled = false;
+            info!("Drill cooled down");
+        }
+    }
+}
+
 pub fn handle_loot_pickup(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
     mut events: EventReader<TileDestroyedEvent>,
     mut player: Query<&mut Inventory, With<Player>>,
 ) {
-    if let Ok(mut inventory) = player.single_mut() {
-        for event in events.read() {
-            if let Some(item) = event.tile_type.to_item() {
-                inventory.add_item(item);
+    for event in events.read() {
+        let Some(item) = event.tile_type.to_item() else {
+            continue;
+        };
+        // Loot goes to whoever broke the tile, anything else falls to the ground
+        let inventory = event
+            .destroyed_by
+            .and_then(|entity| player.get_mut(entity).ok())
+            .filter(|inventory| inventory.has_room_for(item.quantity));
+        match inventory {
+            Some(mut inventory) => inventory.add_item(item),
+            None => {
+                // No room in the hold: the ore is left on the ground for the claw
+                drop_loose_item(
+                    &mut commands,
+                    &game_assets,
+                    event.tile_type,
+                    item,
+                    grid_to_world_position(event.position),
+                );
             }
         }
     }
@@ -85,14 +226,14 @@ pub fn collision_detection(
     mut player: Query<
         (
             &Velocity,
-            &PlayerAttributes,
             &mut DrillState,
             &Transform,
         ),
         With<Player>,
     >,
     tiles: Query<&Transform, With<Tile>>,
-    mut impact_events: EventWriter<PlayerImpactEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut landing_events: EventWriter<LandingEvent>,
 ) {
     for event in collision_events.read() {
         match event {
@@ -106,7 +247,7 @@ pub fn collision_detection(
                         continue;
                     };
 
-                let (velocity, player_attributes, mut drill_state, player_pos) =
+                let (velocity, mut drill_state, player_pos) =
                     player.get_mut(player_entity).unwrap();
                 let tile_transform = tiles.get(tile_entity).unwrap();
 
@@ -118,9 +259,17 @@ pub fn collision_detection(
                 if grid_tile_pos.0 == grid_player_pos.0 && *drill_state != DrillState::Drilling {
                     *drill_state = DrillState::Idle;
                     let impact_speed = velocity.linvel.y.abs();
-                    if impact_speed > 300.0 {
-                        let damage = impact_speed * player_attributes.damage_factor;
-                        impact_events.write(PlayerImpactEvent { impact_speed, damage });
+                    landing_events.write(LandingEvent {
+                        player: player_entity,
+                        speed: impact_speed,
+                    });
+                    if impact_speed > SAFE_LANDING_SPEED {
+                        damage_events.write(DamageEvent {
+                            player: player_entity,
+                            damage_type: DamageType::Impact,
+                            source: "hard landing",
+                            amount: impact_speed * IMPACT_DAMAGE_FACTOR,
+                        });
                     }
                 }
             }
@@ -129,32 +278,26 @@ pub fn collision_detection(
     }
 }
 
-pub fn apply_impact_damage(
-    mut events: EventReader<PlayerImpactEvent>,
-    mut player: Query<(&mut Health, &PlayerAttributes), With<Player>>,
-) {
-    if let Ok((mut health, attributes)) = player.single_mut() {
-        for event in events.read() {
-            let damage_reduction = (1.0 - attributes.armor_resistance.min(0.9)).max(0.1);
-            let actual_damage = event.damage * damage_reduction;
-            health.current -= actual_damage;
-            info!(
-                "Player impact: speed={:.1}, damage={:.1} (reduced to {:.1}), health={:.1}",
-                event.impact_speed, event.damage, actual_damage, health.current
-            );
-        }
-    }
-}
-
+/// Losing any drill ends the run for the whole crew. Remote drills running dry have no rescue
+/// menu, `rescue_remote_drills` deals with them
 pub fn death_detection(
-    player: Query<(&Health, &Fuel), With<Player>>,
+    player: Query<(&PlayerId, &Health, &Fuel, Has<RemoteInput>), With<Player>>,
     mut next_state: ResMut<NextState<GameState>>,
     mut next_menu_state: ResMut<NextState<MenuState>>,
+    mut menu_player: ResMut<MenuPlayer>,
 ) {
-    if let Ok((health, fuel)) = player.single() {
-        if health.current <= 0.0 || fuel.current <= 0.0 {
+    for (player_id, health, fuel, remote) in player.iter() {
+        if health.current <= 0.0 {
+            menu_player.0 = *player_id;
             next_menu_state.set(GameOver);
             next_state.set(GameState::Menu);
+            return;
+        } else if fuel.current <= 0.0 && !remote {
+            // Stranded: the player may still call a tow back to the base
+            menu_player.0 = *player_id;
+            next_menu_state.set(Rescue);
+            next_state.set(GameState::Menu);
+            return;
         }
     }
 }
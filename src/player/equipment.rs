@@ -0,0 +1,354 @@
+#![allow(clippy::type_complexity)]
+
+use crate::player::components::*;
+use crate::player::damage::Resistances;
+use crate::player::status::StatusEffects;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum EquipmentSlot {
+    DrillHead,
+    Engine,
+    FuelTank,
+    Hull,
+    Radiator,
+    CargoBay,
+    Scanner,
+}
+
+impl EquipmentSlot {
+    pub const ALL: [EquipmentSlot; 7] = [
+        EquipmentSlot::DrillHead,
+        EquipmentSlot::Engine,
+        EquipmentSlot::FuelTank,
+        EquipmentSlot::Hull,
+        EquipmentSlot::Radiator,
+        EquipmentSlot::CargoBay,
+        EquipmentSlot::Scanner,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EquipmentSlot::DrillHead => "Drill head",
+            EquipmentSlot::Engine => "Engine",
+            EquipmentSlot::FuelTank => "Fuel tank",
+            EquipmentSlot::Hull => "Hull",
+            EquipmentSlot::Radiator => "Radiator",
+            EquipmentSlot::CargoBay => "Cargo bay",
+            EquipmentSlot::Scanner => "Scanner",
+        }
+    }
+}
+
+/// Bonuses a module adds on top of the stock drilling machine
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct StatModifiers {
+    pub drill_power: f32,
+    pub drill_tier: u8,
+    pub acceleration: f32,
+    pub thrust: f32,
+    pub max_speed: f32,
+    pub max_climb_speed: f32,
+    pub fuel_efficiency: f32,
+    pub resistances: Resistances,
+    pub fuel_capacity: f32,
+    pub cooling_rate: f32,
+    pub cargo_capacity: usize,
+    pub scanner_range: i32,
+}
+
+impl StatModifiers {
+    pub fn combine(self, other: StatModifiers) -> StatModifiers {
+        StatModifiers {
+            drill_power: self.drill_power + other.drill_power,
+            drill_tier: self.drill_tier + other.drill_tier,
+            acceleration: self.acceleration + other.acceleration,
+            thrust: self.thrust + other.thrust,
+            max_speed: self.max_speed + other.max_speed,
+            max_climb_speed: self.max_climb_speed + other.max_climb_speed,
+            fuel_efficiency: self.fuel_efficiency + other.fuel_efficiency,
+            resistances: self.resistances.combine(other.resistances),
+            fuel_capacity: self.fuel_capacity + other.fuel_capacity,
+            cooling_rate: self.cooling_rate + other.cooling_rate,
+            cargo_capacity: self.cargo_capacity + other.cargo_capacity,
+            scanner_range: self.scanner_range + other.scanner_range,
+        }
+    }
+
+    /// Short list of the non-zero bonuses, used to compare modules in the base menu
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if self.drill_power != 0.0 {
+            parts.push(format!("{:+.1} power", self.drill_power));
+        }
+        if self.drill_tier != 0 {
+            parts.push(format!("+{} tier", self.drill_tier));
+        }
+        if self.max_speed != 0.0 {
+            parts.push(format!("{:+.0} speed", self.max_speed));
+        }
+        if self.thrust != 0.0 {
+            parts.push(format!("{:+.0} thrust", self.thrust));
+        }
+        if self.fuel_efficiency != 0.0 {
+            parts.push(format!("{:+.2} efficiency", self.fuel_efficiency));
+        }
+        parts.extend(self.resistances.describe());
+        if self.fuel_capacity != 0.0 {
+            parts.push(format!("{:+.0} fuel", self.fuel_capacity));
+        }
+        if self.cooling_rate != 0.0 {
+            parts.push(format!("{:+.0} cooling", self.cooling_rate));
+        }
+        if self.cargo_capacity != 0 {
+            parts.push(format!("+{} cargo", self.cargo_capacity));
+        }
+        if self.scanner_range != 0 {
+            parts.push(format!("{:+} range", self.scanner_range));
+        }
+        parts.join(", ")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Module {
+    pub name: String,
+    pub slot: EquipmentSlot,
+    pub cost: u32,
+    pub modifiers: StatModifiers,
+}
+
+impl Module {
+    pub fn new(name: &str, slot: EquipmentSlot, cost: u32, modifiers: StatModifiers) -> Self {
+        Self {
+            name: name.to_string(),
+            slot,
+            cost,
+            modifiers,
+        }
+    }
+
+    /// Module the drilling machine leaves the factory with, it adds nothing
+    pub fn stock(slot: EquipmentSlot) -> Self {
+        Self::new(
+            &format!("Stock {}", slot.name().to_lowercase()),
+            slot,
+            0,
+            StatModifiers::default(),
+        )
+    }
+}
+
+/// Modules installed on the drilling machine, one per slot, plus the ones kept in storage
+#[derive(Component, Debug)]
+pub struct Equipment {
+    pub slots: HashMap<EquipmentSlot, Module>,
+    pub stash: Vec<Module>,
+}
+
+impl Default for Equipment {
+    fn default() -> Self {
+        Self {
+            slots: EquipmentSlot::ALL
+                .into_iter()
+                .map(|slot| (slot, Module::stock(slot)))
+                .collect(),
+            stash: Vec::new(),
+        }
+    }
+}
+
+impl Equipment {
+    pub fn equipped(&self, slot: EquipmentSlot) -> Option<&Module> {
+        self.slots.get(&slot)
+    }
+
+    /// Installs the module, the replaced one goes to storage
+    pub fn equip(&mut self, module: Module) {
+        if let Some(replaced) = self.slots.insert(module.slot, module) {
+            self.stash.push(replaced);
+        }
+    }
+
+    pub fn owns(&self, name: &str) -> bool {
+        self.slots.values().chain(self.stash.iter()).any(|m| m.name == name)
+    }
+
+    /// Swaps the installed module with the next stored one for the same slot
+    pub fn cycle(&mut self, slot: EquipmentSlot) {
+        if let Some(index) = self.stash.iter().position(|m| m.slot == slot) {
+            let module = self.stash.remove(index);
+            self.equip(module);
+        }
+    }
+
+    pub fn modifiers(&self) -> StatModifiers {
+        self.slots
+            .values()
+            .map(|m| m.modifiers)
+            .fold(StatModifiers::default(), StatModifiers::combine)
+    }
+}
+
+/// Modules sold at the base, in upgrade order within each slot
+#[derive(Resource)]
+pub struct ModuleCatalog {
+    pub modules: Vec<Module>,
+}
+
+impl Default for ModuleCatalog {
+    fn default() -> Self {
+        use EquipmentSlot::*;
+        let modules = vec![
+            Module::new("Reinforced bit", DrillHead, 80, StatModifiers {
+                drill_power: 0.5,
+                ..default()
+            }),
+            Module::new("Hardened head", DrillHead, 150, StatModifiers {
+                drill_power: 0.5,
+                drill_tier: 1,
+                ..default()
+            }),
+            Module::new("Diamond head", DrillHead, 400, StatModifiers {
+                drill_power: 1.0,
+                drill_tier: 2,
+                ..default()
+            }),
+            Module::new("Turbo engine", Engine, 60, StatModifiers {
+                acceleration: 150.0,
+                thrust: 150.0,
+                max_speed: 20.0,
+                max_climb_speed: 20.0,
+                ..default()
+            }),
+            Module::new("Jet engine", Engine, 180, StatModifiers {
+                acceleration: 300.0,
+                thrust: 400.0,
+                max_speed: 40.0,
+                max_climb_speed: 60.0,
+                fuel_efficiency: -0.05,
+                ..default()
+            }),
+            Module::new("Large tank", FuelTank, 100, StatModifiers {
+                fuel_capacity: 50.0,
+                ..default()
+            }),
+            Module::new("Huge tank", FuelTank, 250, StatModifiers {
+                fuel_capacity: 120.0,
+                ..default()
+            }),
+            Module::new("Plated hull", Hull, 70, StatModifiers {
+                resistances: Resistances {
+                    impact: 0.1,
+                    ..default()
+                },
+                ..default()
+            }),
+            Module::new("Armored hull", Hull, 200, StatModifiers {
+                resistances: Resistances {
+                    impact: 0.25,
+                    explosion: 0.2,
+                    ..default()
+                },
+                ..default()
+            }),
+            Module::new("Copper radiator", Radiator, 75, StatModifiers {
+                cooling_rate: 4.0,
+                resistances: Resistances {
+                    heat: 0.1,
+                    ..default()
+                },
+                ..default()
+            }),
+            Module::new("Crystal radiator", Radiator, 200, StatModifiers {
+                cooling_rate: 10.0,
+                resistances: Resistances {
+                    heat: 0.25,
+                    ..default()
+                },
+                ..default()
+            }),
+            Module::new("Extended bay", CargoBay, 90, StatModifiers {
+                cargo_capacity: 5,
+                ..default()
+            }),
+            Module::new("Cargo hold", CargoBay, 220, StatModifiers {
+                cargo_capacity: 12,
+                ..default()
+            }),
+            Module::new("Long-range scanner", Scanner, 90, StatModifiers {
+                scanner_range: 3,
+                ..default()
+            }),
+            Module::new("Deep scanner", Scanner, 220, StatModifiers {
+                scanner_range: 7,
+                ..default()
+            }),
+        ];
+        Self { modules }
+    }
+}
+
+impl ModuleCatalog {
+    /// Cheapest module for the slot that has not been bought yet
+    pub fn next_upgrade(&self, equipment: &Equipment, slot: EquipmentSlot) -> Option<&Module> {
+        self.modules
+            .iter()
+            .find(|m| m.slot == slot && !equipment.owns(&m.name))
+    }
+}
+
+/// Recomputes the drilling machine stats from the stock values, the installed modules and the
+/// running status effects
+pub fn update_player_stats(
+    mut player: Query<
+        (
+            &Equipment,
+            &StatusEffects,
+            &mut PlayerAttributes,
+            &mut Fuel,
+            &mut Inventory,
+            &mut Scanner,
+            &mut DrillHeat,
+            &mut Resistances,
+        ),
+        (
+            With<Player>,
+            Or<(Changed<Equipment>, Changed<StatusEffects>)>,
+        ),
+    >,
+) {
+    for (
+        equipment,
+        status_effects,
+        mut attributes,
+        mut fuel,
+        mut inventory,
+        mut scanner,
+        mut heat,
+        mut resistances,
+    ) in player.iter_mut()
+    {
+        let modifiers = equipment.modifiers();
+        let stock = PlayerAttributes::default();
+        attributes.drill_power = stock.drill_power + modifiers.drill_power;
+        attributes.drill_tier = (stock.drill_tier + modifiers.drill_tier).min(MAX_DRILL_TIER);
+        attributes.acceleration = stock.acceleration + modifiers.acceleration;
+        attributes.thrust = stock.thrust + modifiers.thrust;
+        attributes.max_speed = stock.max_speed + modifiers.max_speed;
+        attributes.max_climb_speed = stock.max_climb_speed + modifiers.max_climb_speed;
+        attributes.fuel_efficiency = stock.fuel_efficiency + modifiers.fuel_efficiency;
+
+        fuel.max = Fuel::default().max + modifiers.fuel_capacity;
+        fuel.current = fuel.current.min(fuel.max);
+        inventory.set_capacity(Inventory::default().capacity() + modifiers.cargo_capacity);
+        *resistances = modifiers.resistances;
+        scanner.range = Scanner::default().range + modifiers.scanner_range;
+        let stock_heat = DrillHeat::default();
+        heat.cooling_rate = stock_heat.cooling_rate + modifiers.cooling_rate;
+        heat.heating_rate = stock_heat.heating_rate;
+
+        status_effects.modify(&mut attributes, &mut heat);
+    }
+}
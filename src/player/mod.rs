@@ -1,29 +1,62 @@
 pub mod components;
+pub mod consumables;
+pub mod damage;
 pub mod drilling;
+pub mod equipment;
 pub mod movement;
+pub mod recall;
+pub mod scanner;
+pub mod status;
+pub mod tools;
 
 pub use components::*;
+pub use consumables::*;
+pub use damage::*;
 pub use drilling::*;
+pub use equipment::*;
 pub use movement::*;
+pub use recall::*;
+pub use scanner::*;
+pub use status::*;
+pub use tools::*;
 
 use crate::map::handle_tile_destroyed;
 use crate::prelude::GameSystems::Rendering;
-use crate::prelude::GameState;
+use crate::prelude::{GameState, simulates_world};
 use bevy::prelude::*;
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<PlayerImpactEvent>()
+        app.add_event::<DamageEvent>()
+            .add_event::<DrillBlockedEvent>()
+            .add_event::<LandingEvent>()
+            .add_event::<ApplyStatusEvent>()
+            .init_resource::<DrillRules>()
+            .init_resource::<ModuleCatalog>()
+            .init_resource::<DamageLog>()
             .add_systems(
                 OnEnter(GameState::Rendering),
                 spawn_player.in_set(Rendering),
             )
             .add_systems(
                 FixedUpdate,
-                (move_player, drill, handle_tile_destroyed, handle_loot_pickup, falling_detection)
-                    .run_if(in_state(GameState::Playing))
+                (
+                    move_player,
+                    drill,
+                    fire_laser,
+                    update_drill_heat,
+                    tick_status_effects,
+                    handle_tile_destroyed,
+                    detonate_dynamite,
+                    detach_grapple_from_destroyed_tiles,
+                    reel_grapple,
+                    handle_loot_pickup,
+                    apply_cargo_mass,
+                    falling_detection,
+                )
+                    .run_if(in_state(GameState::Playing).and(simulates_world))
                     .chain(),
             )
             .add_systems(
@@ -32,11 +65,37 @@ impl Plugin for PlayerPlugin {
                     update_player_on_state_changes,
                     update_player_direction,
                     collision_detection,
-                    apply_impact_damage,
+                    apply_damage,
                     death_detection,
                 )
-                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(GameState::Playing).and(simulates_world))
                     .chain(),
-            );
+            )
+            .add_systems(
+                Update,
+                (scan, fade_scan_highlights).run_if(in_state(GameState::Playing).and(simulates_world)),
+            )
+            .add_systems(
+                Update,
+                (
+                    select_tool,
+                    place_dynamite,
+                    grab_with_claw,
+                    fire_grapple,
+                    update_tool_state,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing).and(simulates_world)),
+            )
+            .add_systems(
+                Update,
+                (use_consumable, recall).run_if(in_state(GameState::Playing).and(simulates_world)),
+            )
+            .add_systems(Update, (receive_status_effects, update_player_stats).chain())
+            .add_systems(
+                OnEnter(GameState::Rendering),
+                (clear_dropped_entities, reset_damage_log),
+            )
+            .add_systems(OnExit(GameState::Playing), cut_engine);
     }
 }
@@ -1,23 +1,39 @@
+#![allow(clippy::type_complexity)]
+
+use crate::controls::{Action, PlayerActions};
+use crate::game::CoopConfig;
 use crate::map::TILE_SIZE;
 use crate::player::components::*;
 use crate::prelude::{DrillAnimation, GameAssets, LoadingProgress};
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::{
-    ActiveEvents, Collider, Damping, GravityScale, LockedAxes, QueryFilter, ReadRapierContext,
-    RigidBody, ShapeCastOptions, Velocity,
+    ActiveEvents, Collider, ColliderMassProperties, Damping, ExternalForce, GravityScale,
+    LockedAxes, QueryFilter, ReadMassProperties, ReadRapierContext, RigidBody, ShapeCastOptions,
+    Velocity,
 };
 use bevy_rapier2d::rapier::prelude::SharedShape;
 
+/// Room left between the drills of the local players at the start of the run
+const PLAYER_SPACING: f32 = TILE_SIZE * 2.0;
+
 pub fn spawn_player(
     player: Query<Entity, With<Player>>,
     mut commands: Commands,
     game_assets: Res<GameAssets>,
+    coop: Res<CoopConfig>,
     mut loading_progress: ResMut<LoadingProgress>,
 ) {
-    if let Ok(entity) = player.single() {
+    for entity in player.iter() {
         commands.entity(entity).despawn();
     }
-    info!("Spawning Drilling Machine (Player)");
+    for id in 0..coop.players {
+        spawn_drilling_machine(&mut commands, &game_assets, PlayerId(id));
+    }
+    loading_progress.spawning_player = true;
+}
+
+fn spawn_drilling_machine(commands: &mut Commands, game_assets: &GameAssets, player_id: PlayerId) {
+    info!("Spawning Drilling Machine ({})", player_id.name());
     commands
         .spawn((
             Player,
@@ -30,8 +46,14 @@ pub fn spawn_player(
             PlayerAttributes::default(),
             PlayerDirection::default(),
             DrillAnimation::default(),
+            Scanner::default(),
+            DrillHeat::default(),
+            ToolBelt::default(),
+            ToolState::default(),
+            Hotbar::default(),
         ))
         .insert((
+            player_id,
             Damping {
                 linear_damping: 0.5,
                 angular_damping: 0.5,
@@ -42,69 +64,122 @@ pub fn spawn_player(
                     layout: game_assets.player.texture_layout.clone(),
                     index: 0,
                 }),
+                color: player_id.color(),
                 custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
                 ..default()
             },
-            Transform::from_xyz(0.0, 25.0, 0.0),
+            Transform::from_xyz(player_id.0 as f32 * PLAYER_SPACING, 25.0, 0.0),
             RigidBody::Dynamic,
             Collider::capsule_y((TILE_SIZE - 28.0) / 2f32, 14.0),
+            ColliderMassProperties::default(),
             ActiveEvents::COLLISION_EVENTS,
             GravityScale(1.0),
             Velocity::zero(),
+            ExternalForce::default(),
+            ReadMassProperties::default(),
             LockedAxes::ROTATION_LOCKED,
         ));
-    loading_progress.spawning_player = true;
 }
 
 pub fn move_player(
     time: Res<Time<Fixed>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
     mut query_player: Query<
-        (&mut Velocity, &mut DrillState, &PlayerAttributes, &mut Fuel),
+        (
+            &PlayerActions,
+            &Velocity,
+            &ReadMassProperties,
+            &mut ExternalForce,
+            &mut Damping,
+            &mut DrillState,
+            &PlayerAttributes,
+            &mut Fuel,
+            &Inventory,
+            &ToolBelt,
+        ),
         With<Player>,
     >,
 ) {
-    if let Ok((mut velocity, mut drill_state, attributes, mut fuel)) = query_player.single_mut() {
-        let direction = keyboard_input
-            .get_pressed()
-            .fold(Vec2::ZERO, |mut direction, key| {
-                match key {
-                    KeyCode::ArrowLeft => direction.x -= 1.0,
-                    KeyCode::ArrowRight => direction.x += 1.0,
-                    KeyCode::ArrowUp => direction.y += 1.0,
-                    _ => (),
-                }
-                direction
-            });
-        if direction != Vec2::ZERO {
-            if direction.x != 0.0 {
-                velocity.linvel.x = direction.x * attributes.ground_speed_factor;
-            }
-            if direction.y != 0.0 {
-                velocity.linvel.y = direction.y * attributes.flying_speed_factor;
-                *drill_state = DrillState::Flying;
+    for (
+        actions,
+        velocity,
+        mass_properties,
+        mut external_force,
+        mut damping,
+        mut drill_state,
+        attributes,
+        mut fuel,
+        inventory,
+        tool_belt,
+    ) in query_player.iter_mut()
+    {
+        let mut direction = Vec2::new(actions.axes.horizontal, actions.axes.thrust);
+        // Hanging from the grappling hook thrust reels the rope instead of firing the engine
+        if tool_belt.grapple.is_some() {
+            direction.y = 0.0;
+        }
+
+        // The engine only pushes until the drill reaches its top speed, a half-tilted stick
+        // gives half the push and half the top speed
+        let mut acceleration = Vec2::ZERO;
+        if direction.x != 0.0
+            && velocity.linvel.x * direction.x.signum() < attributes.max_speed * direction.x.abs()
+        {
+            acceleration.x = direction.x * attributes.acceleration;
+        }
+        if direction.y > 0.0 {
+            *drill_state = DrillState::Flying;
+            if velocity.linvel.y < attributes.max_climb_speed {
+                acceleration.y = direction.y * attributes.thrust;
             }
-            fuel.current -= (1.0 / attributes.fuel_efficiency) * time.delta_secs();
         }
+        // The engine is sized for the empty hull, cargo makes the drill sluggish
+        let load_factor = inventory.load_factor(attributes.hull_mass);
+        external_force.force = acceleration * mass_properties.mass / load_factor;
+
+        damping.linear_damping = if direction == Vec2::ZERO && *drill_state == DrillState::Idle {
+            attributes.brake_damping
+        } else {
+            attributes.drag
+        };
+
+        // Fuel burns proportionally to the engine power actually in use and to the cargo hauled
+        let engine_load = (acceleration.length() / attributes.thrust).min(1.0);
+        fuel.current -=
+            engine_load * load_factor * (1.0 / attributes.fuel_efficiency) * time.delta_secs();
     }
 }
 
-pub fn update_player_on_state_changes(
-    mut query: Query<
-        (&DrillState, &mut Damping, &mut Sprite),
-        (With<Player>, Changed<DrillState>),
+pub fn apply_cargo_mass(
+    mut query_player: Query<
+        (&Inventory, &PlayerAttributes, &mut ColliderMassProperties),
+        (With<Player>, Changed<Inventory>),
     >,
 ) {
-    if let Ok((state, mut damping, mut sprite)) = query.single_mut() {
+    for (inventory, attributes, mut collider_mass) in query_player.iter_mut() {
+        let load_factor = inventory.load_factor(attributes.hull_mass);
+        *collider_mass = ColliderMassProperties::Density(load_factor);
         debug!(
-            "update_player_on_state_changes {{ DrillState: {:?}, Damping: {:?} }}",
-            state, damping
+            "Cargo mass {:.1}, load factor {:.2}",
+            inventory.total_mass(),
+            load_factor
+        );
+    }
+}
+
+pub fn cut_engine(mut query_player: Query<&mut ExternalForce, With<Player>>) {
+    for mut external_force in query_player.iter_mut() {
+        external_force.force = Vec2::ZERO;
+    }
+}
+
+pub fn update_player_on_state_changes(
+    mut query: Query<(&DrillState, &mut Sprite), (With<Player>, Changed<DrillState>)>,
+) {
+    for (state, mut sprite) in query.iter_mut() {
+        debug!(
+            "update_player_on_state_changes {{ DrillState: {:?} }}",
+            state
         );
-        if *state == DrillState::Idle {
-            damping.linear_damping = 10.0;
-        } else {
-            damping.linear_damping = 0.5;
-        }
         if let Some(texture_sprite) = &mut sprite.texture_atlas {
             match state {
                 DrillState::Idle | DrillState::Falling => texture_sprite.index = 0,
@@ -116,16 +191,16 @@ pub fn update_player_on_state_changes(
 }
 
 pub fn update_player_direction(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut player_query: Query<(&mut Transform, &mut PlayerDirection, &mut Sprite), With<Player>>,
+    mut player_query: Query<(&PlayerActions, &mut PlayerDirection, &mut Sprite), With<Player>>,
 ) {
-    if let Ok((_transform, mut direction, mut sprite)) = player_query.single_mut() {
-        if keyboard_input.pressed(KeyCode::ArrowLeft) {
+    for (actions, mut direction, mut sprite) in player_query.iter_mut() {
+        let actions = &actions.buttons;
+        if actions.pressed(Action::MoveLeft) {
             if *direction != PlayerDirection::Left {
                 *direction = PlayerDirection::Left;
                 sprite.flip_x = true;
             }
-        } else if keyboard_input.pressed(KeyCode::ArrowRight) {
+        } else if actions.pressed(Action::MoveRight) {
             if *direction != PlayerDirection::Right {
                 *direction = PlayerDirection::Right;
                 sprite.flip_x = false;
@@ -138,7 +213,7 @@ pub fn falling_detection(
     mut player_query: Query<(&Velocity, &Transform, &mut DrillState), With<Player>>,
     read_rapier_context: ReadRapierContext,
 ) {
-    if let Ok((velocity, transform, mut drill_state)) = player_query.single_mut() {
+    for (velocity, transform, mut drill_state) in player_query.iter_mut() {
         let player_pos = transform.translation.truncate();
 
         if let Ok(context) = read_rapier_context.single() {
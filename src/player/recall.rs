@@ -0,0 +1,101 @@
+#![allow(clippy::type_complexity)]
+
+use crate::controls::{Action, PlayerActions};
+use crate::map::world_to_grid_position;
+use crate::player::components::*;
+use crate::player::damage::DamageEvent;
+use crate::player::tools::release_grapple;
+use crate::world_base::WorldBase;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Velocity;
+
+/// Above this speed (px/s) the drill is considered moving and the recall is cancelled
+const RECALL_MAX_SPEED: f32 = 20.0;
+
+pub fn recall(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    mut player: Query<
+        (
+            Entity,
+            &PlayerActions,
+            &mut RecallDevice,
+            &mut Fuel,
+            &mut Transform,
+            &mut Velocity,
+            &mut ToolBelt,
+            &mut ToolState,
+        ),
+        With<Player>,
+    >,
+    world_base: Query<&GlobalTransform, With<WorldBase>>,
+) {
+    let damaged: Vec<Entity> = damage_events.read().map(|event| event.player).collect();
+    for (
+        entity,
+        actions,
+        mut device,
+        mut fuel,
+        mut transform,
+        mut velocity,
+        mut tool_belt,
+        mut tool_state,
+    ) in player.iter_mut()
+    {
+        device.cooldown.tick(time.delta());
+        let moving = actions.axes.horizontal != 0.0
+            || actions.axes.thrust != 0.0
+            || velocity.linvel.length() > RECALL_MAX_SPEED;
+
+        let Some(channel) = device.channel.as_mut() else {
+            if !actions.buttons.just_pressed(Action::Recall) || !device.owned {
+                continue;
+            }
+            if !device.cooldown.finished() {
+                info!(
+                    "Recall device recharging: {:.1}s left",
+                    device.cooldown.remaining_secs()
+                );
+                continue;
+            }
+            let depth = -world_to_grid_position(transform.translation.truncate()).1;
+            if fuel.current <= device.fuel_cost_at(depth) {
+                info!("Not enough fuel to recall");
+                continue;
+            }
+            if moving {
+                info!("Hold still to recall");
+                continue;
+            }
+            info!("Recall charging up");
+            device.channel = Some(Timer::from_seconds(device.channel_secs, TimerMode::Once));
+            continue;
+        };
+
+        if moving || damaged.contains(&entity) {
+            info!("Recall cancelled");
+            device.channel = None;
+            continue;
+        }
+        if !channel.tick(time.delta()).finished() {
+            continue;
+        }
+        device.channel = None;
+        let Ok(base_transform) = world_base.single() else {
+            continue;
+        };
+        let depth = -world_to_grid_position(transform.translation.truncate()).1;
+        fuel.current -= device.fuel_cost_at(depth);
+        device.cooldown.reset();
+        // The rope would drag the drill straight back down
+        if tool_belt.grapple.is_some() {
+            release_grapple(&mut commands, entity, &mut tool_belt, &mut tool_state);
+        }
+        // Landing on the base entrance opens the base menu
+        let base_position = base_transform.translation().truncate();
+        transform.translation = base_position.extend(transform.translation.z);
+        *velocity = Velocity::zero();
+        info!("Recalled to the base from depth {}", depth);
+    }
+}
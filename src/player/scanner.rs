@@ -0,0 +1,140 @@
+use crate::controls::{Action, PlayerActions};
+use crate::map::{TILE_SIZE, TileType, WorldGrid, world_to_grid_position};
+use crate::player::components::*;
+use bevy::prelude::*;
+
+/// Below this strength an ore echo is too weak to be shown.
+const MIN_SCAN_STRENGTH: f32 = 0.05;
+/// How much of the signal is absorbed for each unit of hardness crossed by the pulse.
+const ROCK_ABSORPTION: f32 = 0.8;
+
+/// Temporary overlay spawned as a child of an ore tile hit by a scanner pulse.
+#[derive(Component)]
+pub struct ScanHighlight {
+    pub strength: f32,
+    pub timer: Timer,
+}
+
+pub fn scan(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut player: Query<(&PlayerActions, &Transform, &mut Scanner, &mut Fuel), With<Player>>,
+    world_grid: Res<WorldGrid>,
+) {
+    for (actions, transform, mut scanner, mut fuel) in player.iter_mut() {
+        scanner.cooldown.tick(time.delta());
+        if !actions.buttons.just_pressed(Action::Scan) {
+            continue;
+        }
+        if !scanner.cooldown.finished() {
+            info!(
+                "Scanner recharging: {:.1}s left",
+                scanner.cooldown.remaining_secs()
+            );
+            continue;
+        }
+        if fuel.current <= scanner.fuel_cost {
+            info!("Not enough fuel to scan");
+            continue;
+        }
+        fuel.current -= scanner.fuel_cost;
+        scanner.cooldown.reset();
+
+        let origin = world_to_grid_position(transform.translation.truncate());
+        let range = scanner.range;
+        let mut echoes = 0;
+        for dy in -range..=range {
+            for dx in -range..=range {
+                let distance = ((dx * dx + dy * dy) as f32).sqrt();
+                if distance > range as f32 {
+                    continue;
+                }
+                let position = (origin.0 + dx, origin.1 + dy);
+                let Some(tile_type) = world_grid.tile_type_at(position) else {
+                    continue;
+                };
+                // Ores already in sight don't need to be highlighted
+                if !tile_type.is_ore() || world_grid.revealed_tiles.contains(&position) {
+                    continue;
+                }
+                let Some(&tile_entity) = world_grid.grid.get(&position) else {
+                    continue;
+                };
+                let strength = signal_strength(&world_grid, origin, position, range);
+                if strength < MIN_SCAN_STRENGTH {
+                    continue;
+                }
+                commands.entity(tile_entity).with_child((
+                    ScanHighlight {
+                        strength,
+                        timer: Timer::from_seconds(scanner.highlight_duration, TimerMode::Once),
+                    },
+                    Sprite {
+                        color: scan_color(tile_type).with_alpha(strength),
+                        custom_size: Some(Vec2::splat(TILE_SIZE)),
+                        ..default()
+                    },
+                    Transform::from_xyz(0.0, 0.0, 0.5),
+                ));
+                echoes += 1;
+            }
+        }
+        info!("Scanner pulse from {:?}: {} ore echoes", origin, echoes);
+    }
+}
+
+pub fn fade_scan_highlights(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut highlights: Query<(Entity, &mut ScanHighlight, &mut Sprite)>,
+) {
+    for (entity, mut highlight, mut sprite) in highlights.iter_mut() {
+        highlight.timer.tick(time.delta());
+        if highlight.timer.finished() {
+            commands.entity(entity).despawn();
+        } else {
+            let alpha = highlight.strength * highlight.timer.fraction_remaining();
+            sprite.color.set_alpha(alpha);
+        }
+    }
+}
+
+/// Signal returned by an ore tile: it decays linearly with distance and is absorbed by the
+/// hardness of every tile crossed between the scanner and the target, the target included.
+fn signal_strength(
+    world_grid: &WorldGrid,
+    origin: (i32, i32),
+    target: (i32, i32),
+    range: i32,
+) -> f32 {
+    let from = Vec2::new(origin.0 as f32, origin.1 as f32);
+    let to = Vec2::new(target.0 as f32, target.1 as f32);
+    let distance = from.distance(to);
+    let distance_falloff = 1.0 - distance / (range as f32 + 1.0);
+
+    let steps = distance.ceil() as i32;
+    let mut absorbed = 0.0;
+    let mut last_crossed = origin;
+    for step in 1..=steps {
+        let point = from.lerp(to, step as f32 / steps as f32).round();
+        let crossed = (point.x as i32, point.y as i32);
+        if crossed == last_crossed {
+            continue;
+        }
+        last_crossed = crossed;
+        if let Some(tile_type) = world_grid.tile_type_at(crossed) {
+            absorbed += tile_type.drilling().hardness;
+        }
+    }
+    (distance_falloff * (1.0 - absorbed * ROCK_ABSORPTION)).clamp(0.0, 1.0)
+}
+
+fn scan_color(tile_type: TileType) -> Color {
+    match tile_type {
+        TileType::Iron => Color::srgb(0.8, 0.45, 0.35),
+        TileType::Copper => Color::srgb(0.95, 0.55, 0.15),
+        TileType::Gold => Color::srgb(1.0, 0.85, 0.1),
+        TileType::Crystal => Color::srgb(0.4, 0.9, 1.0),
+        _ => Color::WHITE,
+    }
+}
@@ -0,0 +1,174 @@
+use crate::player::components::*;
+use crate::player::damage::{DamageEvent, DamageType};
+use bevy::prelude::*;
+
+/// Health lost per second for each stack of burning
+const BURN_DAMAGE_PER_SEC: f32 = 3.0;
+/// Fuel lost per second for each stack of leaking
+const FUEL_LEAK_PER_SEC: f32 = 2.0;
+const SLOWED_FACTOR: f32 = 0.5;
+const OVERCLOCK_POWER_FACTOR: f32 = 1.5;
+const OVERCLOCK_HEATING_FACTOR: f32 = 1.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StatusKind {
+    Burning,
+    Slowed,
+    LeakingFuel,
+    Shielded,
+    Overclocked,
+}
+
+impl StatusKind {
+    pub const ALL: [StatusKind; 5] = [
+        StatusKind::Burning,
+        StatusKind::Slowed,
+        StatusKind::LeakingFuel,
+        StatusKind::Shielded,
+        StatusKind::Overclocked,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatusKind::Burning => "Burning",
+            StatusKind::Slowed => "Slowed",
+            StatusKind::LeakingFuel => "Leaking fuel",
+            StatusKind::Shielded => "Shielded",
+            StatusKind::Overclocked => "Overclocked",
+        }
+    }
+
+    /// How many times the effect can pile up, effects with a single stack are refreshed instead
+    pub fn max_stacks(&self) -> u32 {
+        match self {
+            StatusKind::Burning => 3,
+            StatusKind::LeakingFuel => 3,
+            StatusKind::Slowed | StatusKind::Shielded | StatusKind::Overclocked => 1,
+        }
+    }
+}
+
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub stacks: u32,
+    pub timer: Timer,
+}
+
+/// Timed effects currently affecting the drilling machine
+#[derive(Component, Default)]
+pub struct StatusEffects {
+    pub effects: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    /// Adds a stack to an effect already running, up to its maximum, and restarts its timer
+    pub fn apply(&mut self, kind: StatusKind, duration: f32) {
+        if let Some(effect) = self.effects.iter_mut().find(|e| e.kind == kind) {
+            effect.stacks = (effect.stacks + 1).min(kind.max_stacks());
+            if effect.timer.remaining_secs() < duration {
+                effect.timer = Timer::from_seconds(duration, TimerMode::Once);
+            }
+        } else {
+            self.effects.push(StatusEffect {
+                kind,
+                stacks: 1,
+                timer: Timer::from_seconds(duration, TimerMode::Once),
+            });
+        }
+    }
+
+    pub fn get(&self, kind: StatusKind) -> Option<&StatusEffect> {
+        self.effects.iter().find(|e| e.kind == kind)
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.get(kind).is_some()
+    }
+
+    pub fn stacks(&self, kind: StatusKind) -> u32 {
+        self.get(kind).map_or(0, |e| e.stacks)
+    }
+
+    /// Scales the attributes already computed from the equipment
+    pub fn modify(&self, attributes: &mut PlayerAttributes, heat: &mut DrillHeat) {
+        if self.has(StatusKind::Slowed) {
+            attributes.acceleration *= SLOWED_FACTOR;
+            attributes.thrust *= SLOWED_FACTOR;
+            attributes.max_speed *= SLOWED_FACTOR;
+            attributes.max_climb_speed *= SLOWED_FACTOR;
+        }
+        if self.has(StatusKind::Overclocked) {
+            attributes.drill_power *= OVERCLOCK_POWER_FACTOR;
+            heat.heating_rate *= OVERCLOCK_HEATING_FACTOR;
+        }
+    }
+}
+
+/// Asks for a status effect to be applied to the player, sent by hazards, consumables and creatures
+#[derive(Event)]
+pub struct ApplyStatusEvent {
+    pub player: Entity,
+    pub kind: StatusKind,
+    pub duration: f32,
+}
+
+pub fn receive_status_effects(
+    mut events: EventReader<ApplyStatusEvent>,
+    mut player: Query<&mut StatusEffects, With<Player>>,
+) {
+    for event in events.read() {
+        let Ok(mut status_effects) = player.get_mut(event.player) else {
+            continue;
+        };
+        // A refreshed timer leaves the attributes as they are, only new stacks recompute them
+        let effects = status_effects.bypass_change_detection();
+        let stacks = effects.stacks(event.kind);
+        effects.apply(event.kind, event.duration);
+        if effects.stacks(event.kind) != stacks {
+            status_effects.set_changed();
+        }
+        info!(
+            "{} for {:.1}s (x{})",
+            event.kind.name(),
+            event.duration,
+            status_effects.stacks(event.kind)
+        );
+    }
+}
+
+pub fn tick_status_effects(
+    time: Res<Time<Fixed>>,
+    mut player: Query<(Entity, &mut StatusEffects, &mut Fuel), With<Player>>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    for (entity, mut status_effects, mut fuel) in player.iter_mut() {
+        // Ticking timers is not a change, the attributes only depend on which effects are running
+        let effects = &mut status_effects.bypass_change_detection().effects;
+        for effect in effects.iter_mut() {
+            effect.timer.tick(time.delta());
+            let stacks = effect.stacks as f32;
+            match effect.kind {
+                StatusKind::Burning => {
+                    damage_events.write(DamageEvent {
+                        player: entity,
+                        damage_type: DamageType::Heat,
+                        source: "fire",
+                        amount: BURN_DAMAGE_PER_SEC * stacks * time.delta_secs(),
+                    });
+                }
+                StatusKind::LeakingFuel => {
+                    fuel.current -= FUEL_LEAK_PER_SEC * stacks * time.delta_secs();
+                }
+                _ => {}
+            }
+        }
+        if effects.iter().any(|effect| effect.timer.finished()) {
+            status_effects.effects.retain(|effect| {
+                if effect.timer.finished() {
+                    info!("{} wore off", effect.kind.name());
+                }
+                !effect.timer.finished()
+            });
+        }
+    }
+}
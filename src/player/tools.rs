@@ -0,0 +1,553 @@
+#![allow(clippy::type_complexity)]
+
+use crate::controls::{Action, PlayerActions};
+use crate::map::{
+    TILE_SIZE, Tile, TileDestroyedEvent, TileType, WorldGrid, grid_to_world_position,
+    world_to_grid_position,
+};
+use crate::player::components::*;
+use crate::player::damage::{DamageEvent, DamageType};
+use crate::player::status::{ApplyStatusEvent, StatusKind};
+use crate::prelude::GameAssets;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::{
+    Collider, ImpulseJoint, LockedAxes, QueryFilter, RapierContext, ReadRapierContext, RigidBody,
+    RopeJointBuilder, TypedJoint,
+};
+
+/// Highest tile tier dynamite can blast through
+const DYNAMITE_TIER: u8 = 2;
+const DYNAMITE_FUSE_SECS: f32 = 2.5;
+/// Beyond this many blast radii the player takes no damage
+const DYNAMITE_DAMAGE_REACH: f32 = 1.5;
+/// Being caught in a blast sets the drill on fire and shakes it for this long
+const DYNAMITE_STATUS_SECS: f32 = 3.0;
+/// The grappling hook can't reel the drill closer than this to the anchor
+const GRAPPLE_MIN_LENGTH: f32 = 24.0;
+
+#[derive(Component)]
+pub struct Dynamite {
+    // Drill that placed it, the blasted ores are its loot
+    pub owner: Entity,
+    pub fuse: Timer,
+    pub radius: i32,
+    pub damage: f32,
+}
+
+/// Expanding flash left by a dynamite blast
+#[derive(Component)]
+pub struct Explosion {
+    pub radius: f32,
+    pub timer: Timer,
+}
+
+#[derive(Component)]
+pub struct LaserBeam {
+    pub owner: Entity,
+}
+
+/// Claw reaching for an item, from the drill to `target`
+#[derive(Component)]
+pub struct ClawArm {
+    pub owner: Entity,
+    pub target: Vec2,
+    pub timer: Timer,
+}
+
+#[derive(Component)]
+pub struct GrappleRope {
+    pub owner: Entity,
+}
+
+/// Loot lying on the ground, waiting to be picked up by the claw
+#[derive(Component)]
+pub struct LooseItem {
+    pub item: Item,
+}
+
+pub fn select_tool(
+    mut player: Query<(&PlayerActions, &mut ToolBelt, &mut ToolState), With<Player>>,
+) {
+    for (actions, mut tool_belt, mut tool_state) in player.iter_mut() {
+        if !actions.buttons.just_pressed(Action::NextTool) {
+            continue;
+        }
+        tool_belt.selected = tool_belt.selected.next();
+        tool_belt.laser_hit = None;
+        *tool_state = ToolState::Idle;
+        info!("Selected tool: {}", tool_belt.selected.name());
+    }
+}
+
+pub fn update_tool_state(
+    time: Res<Time>,
+    mut player: Query<(&mut ToolBelt, &mut ToolState), With<Player>>,
+) {
+    for (mut tool_belt, mut tool_state) in player.iter_mut() {
+        if matches!(*tool_state, ToolState::Placing | ToolState::Grabbing)
+            && tool_belt.action.tick(time.delta()).finished()
+        {
+            *tool_state = ToolState::Idle;
+        }
+    }
+}
+
+pub fn place_dynamite(
+    mut commands: Commands,
+    mut player: Query<
+        (Entity, &PlayerActions, &Transform, &mut ToolBelt, &mut ToolState),
+        With<Player>,
+    >,
+) {
+    for (entity, actions, transform, mut tool_belt, mut tool_state) in player.iter_mut() {
+        if !actions.buttons.just_pressed(Action::UseTool)
+            || tool_belt.selected != Tool::Dynamite
+        {
+            continue;
+        }
+        if tool_belt.dynamite == 0 {
+            info!("No dynamite left");
+            continue;
+        }
+        tool_belt.dynamite -= 1;
+        tool_belt.action.reset();
+        *tool_state = ToolState::Placing;
+
+        let grid_position = world_to_grid_position(transform.translation.truncate());
+        commands.spawn((
+            Dynamite {
+                owner: entity,
+                fuse: Timer::from_seconds(DYNAMITE_FUSE_SECS, TimerMode::Once),
+                radius: tool_belt.dynamite_radius,
+                damage: tool_belt.dynamite_damage,
+            },
+            Sprite {
+                color: Color::srgb(0.85, 0.1, 0.1),
+                custom_size: Some(Vec2::new(8.0, 16.0)),
+                ..default()
+            },
+            Transform::from_translation(grid_to_world_position(grid_position).extend(1.0)),
+        ));
+        info!(
+            "Dynamite placed at {:?}, {} left",
+            grid_position, tool_belt.dynamite
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn detonate_dynamite(
+    time: Res<Time<Fixed>>,
+    mut commands: Commands,
+    mut dynamites: Query<(Entity, &mut Dynamite, &Transform)>,
+    player: Query<(Entity, &Transform), With<Player>>,
+    mut world_grid: ResMut<WorldGrid>,
+    mut tile_destroyed_events: EventWriter<TileDestroyedEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut status_events: EventWriter<ApplyStatusEvent>,
+) {
+    for (entity, mut dynamite, transform) in dynamites.iter_mut() {
+        if !dynamite.fuse.tick(time.delta()).finished() {
+            continue;
+        }
+        let blast_center = transform.translation.truncate();
+        let center = world_to_grid_position(blast_center);
+        let radius = dynamite.radius;
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if dx * dx + dy * dy > radius * radius {
+                    continue;
+                }
+                let position = (center.0 + dx, center.1 + dy);
+                let Some(tile_type) = world_grid.tile_type_at(position) else {
+                    continue;
+                };
+                if tile_type == TileType::Empty || tile_type.drilling().min_tier > DYNAMITE_TIER {
+                    continue;
+                }
+                // Tiles already broken by the drill or the laser are gone from the grid, the rest
+                // is claimed right away so an overlapping blast cannot pay the same tile twice
+                let Some(tile_entity) = world_grid.clear_tile(position) else {
+                    continue;
+                };
+                tile_destroyed_events.write(TileDestroyedEvent {
+                    tile_type,
+                    position,
+                    entity: tile_entity,
+                    destroyed_by: Some(dynamite.owner),
+                });
+            }
+        }
+
+        let blast_reach = radius as f32 * TILE_SIZE * DYNAMITE_DAMAGE_REACH;
+        // Every drill caught in the blast is hurt, not only the one that placed it
+        for (player_entity, player_transform) in player.iter() {
+            let distance = player_transform.translation.truncate().distance(blast_center);
+            if distance < blast_reach {
+                damage_events.write(DamageEvent {
+                    player: player_entity,
+                    damage_type: DamageType::Explosion,
+                    source: "dynamite",
+                    amount: dynamite.damage * (1.0 - distance / blast_reach),
+                });
+                for kind in [StatusKind::Burning, StatusKind::Slowed] {
+                    status_events.write(ApplyStatusEvent {
+                        player: player_entity,
+                        kind,
+                        duration: DYNAMITE_STATUS_SECS,
+                    });
+                }
+            }
+        }
+        info!("Dynamite exploded at {:?}", center);
+        commands.entity(entity).despawn();
+        commands.spawn((
+            Explosion {
+                radius: radius as f32 * TILE_SIZE,
+                timer: Timer::from_seconds(0.4, TimerMode::Once),
+            },
+            Sprite {
+                color: Color::srgba(1.0, 0.7, 0.2, 0.9),
+                custom_size: Some(Vec2::splat(TILE_SIZE)),
+                ..default()
+            },
+            Transform::from_translation(blast_center.extend(3.0)),
+        ));
+    }
+}
+
+pub fn fire_laser(
+    time: Res<Time<Fixed>>,
+    mut player: Query<
+        (
+            Entity,
+            &PlayerActions,
+            &Transform,
+            &PlayerDirection,
+            &PlayerAttributes,
+            &mut ToolBelt,
+            &mut ToolState,
+            &mut Fuel,
+        ),
+        With<Player>,
+    >,
+    world_grid: Res<WorldGrid>,
+    mut query_tile: Query<&mut Tile>,
+    mut tile_destroyed_events: EventWriter<TileDestroyedEvent>,
+    mut drill_blocked_events: EventWriter<DrillBlockedEvent>,
+) {
+    for (
+        player_entity,
+        actions,
+        transform,
+        direction,
+        attributes,
+        mut tool_belt,
+        mut tool_state,
+        mut fuel,
+    ) in player.iter_mut()
+    {
+        let actions = &actions.buttons;
+        if tool_belt.selected != Tool::Laser || !actions.pressed(Action::UseTool) {
+            if *tool_state == ToolState::Lasering {
+                *tool_state = ToolState::Idle;
+                tool_belt.laser_hit = None;
+            }
+            continue;
+        }
+        *tool_state = ToolState::Lasering;
+        fuel.current -= tool_belt.laser_fuel_rate * time.delta_secs();
+
+        let origin = transform.translation.truncate();
+        let (dx, dy) = if actions.pressed(Action::DrillDown) {
+            (0, -1)
+        } else if *direction == PlayerDirection::Left {
+            (-1, 0)
+        } else {
+            (1, 0)
+        };
+        let step = Vec2::new(dx as f32, dy as f32);
+        let current_position = world_to_grid_position(origin);
+
+        // Walk the grid along the beam until the first solid tile
+        tool_belt.laser_hit = Some(origin + step * tool_belt.laser_range as f32 * TILE_SIZE);
+        for distance in 1..=tool_belt.laser_range {
+            let position = (
+                current_position.0 + dx * distance,
+                current_position.1 + dy * distance,
+            );
+            let Some(&entity) = world_grid.grid.get(&position) else {
+                continue;
+            };
+            let Ok(mut tile) = query_tile.get_mut(entity) else {
+                continue;
+            };
+            if tile.tile_type == TileType::Empty {
+                continue;
+            }
+            let hit_point = grid_to_world_position(position) - step * TILE_SIZE / 2.0;
+            tool_belt.laser_hit = Some(hit_point);
+
+            if tile.drilling.min_tier > attributes.drill_tier {
+                drill_blocked_events.write(DrillBlockedEvent {
+                    contact_point: hit_point,
+                    required_tier: tile.drilling.min_tier,
+                });
+                break;
+            }
+            tile.drilling.integrity -=
+                tool_belt.laser_power * time.delta_secs() * (1.0 - tile.drilling.hardness);
+            if tile.drilling.integrity <= 0.0 {
+                tile_destroyed_events.write(TileDestroyedEvent {
+                    tile_type: tile.tile_type,
+                    position,
+                    entity,
+                    destroyed_by: Some(player_entity),
+                });
+            }
+            break;
+        }
+    }
+}
+
+pub fn grab_with_claw(
+    mut commands: Commands,
+    mut player: Query<
+        (
+            Entity,
+            &PlayerActions,
+            &Transform,
+            &mut ToolBelt,
+            &mut ToolState,
+            &mut Inventory,
+        ),
+        With<Player>,
+    >,
+    loose_items: Query<(Entity, &Transform, &LooseItem)>,
+    mut grabbed: Local<Vec<Entity>>,
+) {
+    // Two claws grabbing the same item in the same frame: the first one gets it
+    grabbed.clear();
+    for (player_entity, actions, transform, mut tool_belt, mut tool_state, mut inventory) in
+        player.iter_mut()
+    {
+        if !actions.buttons.just_pressed(Action::UseTool) || tool_belt.selected != Tool::Claw {
+            continue;
+        }
+        let origin = transform.translation.truncate();
+        let nearest = loose_items
+            .iter()
+            .map(|(entity, item_transform, loose_item)| {
+                let item_position = item_transform.translation.truncate();
+                (entity, item_position, loose_item, origin.distance(item_position))
+            })
+            .filter(|(entity, _, _, distance)| {
+                *distance <= tool_belt.claw_reach && !grabbed.contains(entity)
+            })
+            .min_by(|a, b| a.3.total_cmp(&b.3));
+
+        let Some((entity, item_position, loose_item, _)) = nearest else {
+            info!("Nothing within the claw reach");
+            continue;
+        };
+        if !inventory.has_room_for(loose_item.item.quantity) {
+            info!("Inventory full!");
+            continue;
+        }
+        info!(
+            "Claw picked up {} x{}",
+            loose_item.item.name, loose_item.item.quantity
+        );
+        inventory.add_item(loose_item.item.clone());
+        commands.entity(entity).despawn();
+        grabbed.push(entity);
+
+        tool_belt.action.reset();
+        *tool_state = ToolState::Grabbing;
+        commands.spawn((
+            ClawArm {
+                owner: player_entity,
+                target: item_position,
+                timer: Timer::from_seconds(0.3, TimerMode::Once),
+            },
+            Sprite {
+                color: Color::srgb(0.7, 0.7, 0.75),
+                custom_size: Some(Vec2::new(1.0, 4.0)),
+                ..default()
+            },
+            Transform::from_translation(origin.extend(1.5)),
+        ));
+    }
+}
+
+pub fn fire_grapple(
+    mut commands: Commands,
+    read_rapier_context: ReadRapierContext,
+    mut player: Query<
+        (Entity, &PlayerActions, &Transform, &mut ToolBelt, &mut ToolState),
+        With<Player>,
+    >,
+    tiles: Query<&Transform, With<Tile>>,
+) {
+    let Ok(context) = read_rapier_context.single() else {
+        return;
+    };
+    for (entity, actions, transform, mut tool_belt, mut tool_state) in player.iter_mut() {
+        fire_player_grapple(
+            &mut commands,
+            &context,
+            &tiles,
+            entity,
+            &actions.buttons,
+            transform,
+            &mut tool_belt,
+            &mut tool_state,
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn fire_player_grapple(
+    commands: &mut Commands,
+    context: &RapierContext,
+    tiles: &Query<&Transform, With<Tile>>,
+    entity: Entity,
+    actions: &ButtonInput<Action>,
+    transform: &Transform,
+    tool_belt: &mut ToolBelt,
+    tool_state: &mut ToolState,
+) {
+    // Putting the hook away lets go of the anchor
+    if tool_belt.selected != Tool::Grapple {
+        if tool_belt.grapple.is_some() {
+            release_grapple(commands, entity, tool_belt, tool_state);
+        }
+        return;
+    }
+    if !actions.just_pressed(Action::UseTool) {
+        return;
+    }
+    if tool_belt.grapple.is_some() {
+        release_grapple(commands, entity, tool_belt, tool_state);
+        return;
+    }
+
+    // Straight up, or slanted towards the pressed arrow
+    let pressed = |action: Action| actions.pressed(action) as i32 as f32;
+    let horizontal = pressed(Action::MoveRight) - pressed(Action::MoveLeft);
+    let direction = Vec2::new(horizontal, 1.0).normalize();
+    let origin = transform.translation.truncate();
+    let filter = QueryFilter::default()
+        .exclude_rigid_body(entity)
+        .exclude_sensors();
+    let hit = context.cast_ray(origin, direction, tool_belt.grapple_range, true, filter);
+    let Some((tile, length)) = hit else {
+        info!("Grappling hook found nothing to hold on to");
+        return;
+    };
+    // Only rock can hold the hook, not the map borders
+    let Ok(tile_transform) = tiles.get(tile) else {
+        return;
+    };
+    let point = origin + direction * length;
+    commands.entity(entity).insert(ImpulseJoint::new(
+        tile,
+        RopeJointBuilder::new(length)
+            .local_anchor1(point - tile_transform.translation.truncate())
+            .local_anchor2(Vec2::ZERO),
+    ));
+    tool_belt.grapple = Some(GrappleAnchor {
+        tile,
+        point,
+        length,
+    });
+    *tool_state = ToolState::Grappling;
+    info!("Grappling hook attached at {:?}", world_to_grid_position(point));
+}
+
+/// Reels the rope in with thrust and out with drill down, without burning fuel
+pub fn reel_grapple(
+    time: Res<Time<Fixed>>,
+    mut player: Query<(&PlayerActions, &mut ToolBelt, &mut ImpulseJoint), With<Player>>,
+) {
+    for (actions, mut tool_belt, mut joint) in player.iter_mut() {
+        let range = tool_belt.grapple_range;
+        let reel_speed = tool_belt.grapple_reel_speed;
+        let Some(anchor) = tool_belt.grapple.as_mut() else {
+            continue;
+        };
+        let pressed = |action: Action| actions.buttons.pressed(action) as i32 as f32;
+        let reel = pressed(Action::DrillDown) - pressed(Action::Thrust);
+        if reel == 0.0 {
+            continue;
+        }
+        anchor.length = (anchor.length + reel * reel_speed * time.delta_secs())
+            .clamp(GRAPPLE_MIN_LENGTH, range);
+        if let TypedJoint::RopeJoint(rope) = &mut joint.data {
+            rope.set_max_distance(anchor.length);
+        }
+    }
+}
+
+pub fn detach_grapple_from_destroyed_tiles(
+    mut commands: Commands,
+    mut events: EventReader<TileDestroyedEvent>,
+    mut player: Query<(Entity, &mut ToolBelt, &mut ToolState), With<Player>>,
+) {
+    let destroyed: Vec<Entity> = events.read().map(|event| event.entity).collect();
+    for (entity, mut tool_belt, mut tool_state) in player.iter_mut() {
+        let Some(anchor) = tool_belt.grapple else {
+            continue;
+        };
+        if destroyed.contains(&anchor.tile) {
+            info!("The grappling hook anchor crumbled");
+            release_grapple(&mut commands, entity, &mut tool_belt, &mut tool_state);
+        }
+    }
+}
+
+pub fn release_grapple(
+    commands: &mut Commands,
+    entity: Entity,
+    tool_belt: &mut ToolBelt,
+    tool_state: &mut ToolState,
+) {
+    commands.entity(entity).remove::<ImpulseJoint>();
+    tool_belt.grapple = None;
+    *tool_state = ToolState::Idle;
+}
+
+pub fn drop_loose_item(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    tile_type: TileType,
+    item: Item,
+    position: Vec2,
+) {
+    commands.spawn((
+        LooseItem { item },
+        Sprite {
+            image: game_assets.terrain.texture.clone(),
+            texture_atlas: Some(TextureAtlas {
+                layout: game_assets.terrain.texture_layout.clone(),
+                index: tile_type.texture_index(),
+            }),
+            custom_size: Some(Vec2::splat(TILE_SIZE / 3.0)),
+            ..default()
+        },
+        Transform::from_translation(position.extend(1.0)),
+        RigidBody::Dynamic,
+        Collider::ball(TILE_SIZE / 6.0),
+        LockedAxes::ROTATION_LOCKED,
+    ));
+}
+
+pub fn clear_dropped_entities(
+    mut commands: Commands,
+    loose_items: Query<Entity, With<LooseItem>>,
+    dynamites: Query<Entity, With<Dynamite>>,
+) {
+    for entity in loose_items.iter().chain(dynamites.iter()) {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,402 @@
+#![allow(clippy::type_complexity)]
+
+use crate::game::GameState;
+use crate::map::{TILE_SIZE, TileDestroyedEvent, TileType, world_to_grid_position};
+use crate::menu::{MenuState, handle_gameover_menu};
+use crate::player::{
+    Currency, DamageEvent, DrillState, Fuel, Health, LandingEvent, Player, apply_damage,
+};
+use crate::prelude::GameState::Playing;
+use crate::prelude::GameSystems::Running;
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+
+/// File the lifetime statistics are loaded from and saved to, next to the game
+const STATS_FILE: &str = "stats.cfg";
+/// Moves longer than this in a single frame are teleports or tows, not flight
+const MAX_FLIGHT_STEP: f32 = TILE_SIZE * 4.0;
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .insert_resource(LifetimeStats::load())
+            .add_systems(OnEnter(GameState::Rendering), reset_run_stats)
+            // Coming back from the trading screen is not a new visit
+            .add_systems(
+                OnTransition {
+                    exited: MenuState::None,
+                    entered: MenuState::WorldBase,
+                },
+                count_base_visit,
+            )
+            // One last look at the player, the killing blow landed after the last tracked frame
+            .add_systems(
+                OnEnter(MenuState::GameOver),
+                (track_run_stats, finish_run)
+                    .chain()
+                    .before(handle_gameover_menu),
+            )
+            .add_systems(
+                Update,
+                track_run_stats.in_set(Running).run_if(in_state(Playing)),
+            )
+            // Only once the landing has hurt the drill is it known whether it was survived
+            .add_systems(
+                Update,
+                track_survived_landings
+                    .after(apply_damage)
+                    .run_if(in_state(Playing)),
+            );
+    }
+}
+
+/// Everything worth counting about the runs, summed or kept at its best
+#[derive(Clone, Default, Debug)]
+pub struct StatTotals {
+    pub tiles_dug: HashMap<TileType, u32>,
+    pub max_depth: i32,
+    pub distance_flown: f32,
+    pub damage_taken: f32,
+    pub hits_taken: u32,
+    pub hardest_hit: f32,
+    pub fastest_landing: f32,
+    // Fastest landing the drill came out of in one piece
+    pub survived_landing: f32,
+    pub fuel_burned: f32,
+    pub money_earned: u32,
+    pub money_spent: u32,
+    pub base_visits: u32,
+    pub time_played: f32,
+}
+
+impl StatTotals {
+    pub fn total_tiles_dug(&self) -> u32 {
+        self.tiles_dug.values().sum()
+    }
+
+    pub fn add(&mut self, other: &StatTotals) {
+        for (tile_type, count) in &other.tiles_dug {
+            *self.tiles_dug.entry(*tile_type).or_default() += count;
+        }
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.distance_flown += other.distance_flown;
+        self.damage_taken += other.damage_taken;
+        self.hits_taken += other.hits_taken;
+        self.hardest_hit = self.hardest_hit.max(other.hardest_hit);
+        self.fastest_landing = self.fastest_landing.max(other.fastest_landing);
+        self.survived_landing = self.survived_landing.max(other.survived_landing);
+        self.fuel_burned += other.fuel_burned;
+        self.money_earned += other.money_earned;
+        self.money_spent += other.money_spent;
+        self.base_visits += other.base_visits;
+        self.time_played += other.time_played;
+    }
+
+    pub fn describe(&self) -> Vec<String> {
+        let ores = TileType::SOLID
+            .into_iter()
+            .filter(|tile_type| tile_type.is_ore())
+            .map(|tile_type| {
+                let count = self.tiles_dug.get(&tile_type).copied().unwrap_or(0);
+                format!("{:?} {}", tile_type, count)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        vec![
+            format!("Tiles dug: {} ({})", self.total_tiles_dug(), ores),
+            format!("Max depth: {}", self.max_depth),
+            format!("Distance flown: {:.0} tiles", self.distance_flown / TILE_SIZE),
+            format!(
+                "Damage taken: {:.0} in {} hits (hardest {:.0})",
+                self.damage_taken, self.hits_taken, self.hardest_hit
+            ),
+            format!("Fastest landing: {:.0}", self.fastest_landing),
+            format!("Fuel burned: {:.0}", self.fuel_burned),
+            format!(
+                "Money earned: {}c, spent: {}c",
+                self.money_earned, self.money_spent
+            ),
+            format!("Base visits: {}", self.base_visits),
+            format!(
+                "Time played: {}m {:02}s",
+                self.time_played as u32 / 60,
+                self.time_played as u32 % 60
+            ),
+        ]
+    }
+}
+
+/// Statistics of the current run, shown on the game over screen
+#[derive(Resource, Default)]
+pub struct RunStats {
+    pub totals: StatTotals,
+    // Set once the run has been added to the lifetime statistics
+    pub finished: bool,
+    // Values seen last frame for each drill, the stats count the differences
+    last_samples: HashMap<Entity, StatSample>,
+}
+
+/// Drill values the run statistics are computed from
+#[derive(Clone, Copy)]
+struct StatSample {
+    position: Vec2,
+    fuel: f32,
+    health: f32,
+    currency: u32,
+}
+
+/// Statistics summed over every finished run, kept between sessions
+#[derive(Resource, Default)]
+pub struct LifetimeStats {
+    pub runs: u32,
+    pub totals: StatTotals,
+}
+
+impl LifetimeStats {
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = vec![format!("Runs: {}", self.runs)];
+        lines.extend(self.totals.describe());
+        lines
+    }
+
+    pub fn load() -> Self {
+        let Ok(contents) = fs::read_to_string(STATS_FILE) else {
+            info!("No {} found, starting lifetime statistics from zero", STATS_FILE);
+            return Self::default();
+        };
+        Self::parse(&contents)
+    }
+
+    fn parse(contents: &str) -> Self {
+        let mut stats = Self::default();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                warn!("Ignoring malformed statistic '{}'", line);
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let Ok(number) = value.parse::<f32>() else {
+                warn!("Ignoring statistic '{}' with value '{}'", key, value);
+                continue;
+            };
+            let totals = &mut stats.totals;
+            match key {
+                "runs" => stats.runs = number as u32,
+                "max_depth" => totals.max_depth = number as i32,
+                "distance_flown" => totals.distance_flown = number,
+                "damage_taken" => totals.damage_taken = number,
+                "hits_taken" => totals.hits_taken = number as u32,
+                "hardest_hit" => totals.hardest_hit = number,
+                "fastest_landing" => totals.fastest_landing = number,
+                "survived_landing" => totals.survived_landing = number,
+                "fuel_burned" => totals.fuel_burned = number,
+                "money_earned" => totals.money_earned = number as u32,
+                "money_spent" => totals.money_spent = number as u32,
+                "base_visits" => totals.base_visits = number as u32,
+                "time_played" => totals.time_played = number,
+                _ => {
+                    let tile_type = key.strip_prefix("tiles_dug_").and_then(|name| {
+                        TileType::SOLID
+                            .into_iter()
+                            .find(|tile_type| format!("{:?}", tile_type).to_lowercase() == name)
+                    });
+                    match tile_type {
+                        Some(tile_type) => {
+                            totals.tiles_dug.insert(tile_type, number as u32);
+                        }
+                        None => warn!("Ignoring unknown statistic '{}'", key),
+                    }
+                }
+            }
+        }
+        stats
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = fs::write(STATS_FILE, self.to_cfg()) {
+            warn!("Cannot save statistics to {}: {}", STATS_FILE, error);
+        }
+    }
+
+    fn to_cfg(&self) -> String {
+        let totals = &self.totals;
+        let mut contents = String::from("# Lifetime statistics\n");
+        contents.push_str(&format!("runs = {}\n", self.runs));
+        for tile_type in TileType::SOLID {
+            let count = totals.tiles_dug.get(&tile_type).copied().unwrap_or(0);
+            let name = format!("{:?}", tile_type).to_lowercase();
+            contents.push_str(&format!("tiles_dug_{} = {}\n", name, count));
+        }
+        contents.push_str(&format!("max_depth = {}\n", totals.max_depth));
+        contents.push_str(&format!("distance_flown = {}\n", totals.distance_flown));
+        contents.push_str(&format!("damage_taken = {}\n", totals.damage_taken));
+        contents.push_str(&format!("hits_taken = {}\n", totals.hits_taken));
+        contents.push_str(&format!("hardest_hit = {}\n", totals.hardest_hit));
+        contents.push_str(&format!("fastest_landing = {}\n", totals.fastest_landing));
+        contents.push_str(&format!("survived_landing = {}\n", totals.survived_landing));
+        contents.push_str(&format!("fuel_burned = {}\n", totals.fuel_burned));
+        contents.push_str(&format!("money_earned = {}\n", totals.money_earned));
+        contents.push_str(&format!("money_spent = {}\n", totals.money_spent));
+        contents.push_str(&format!("base_visits = {}\n", totals.base_visits));
+        contents.push_str(&format!("time_played = {}\n", totals.time_played));
+        contents
+    }
+}
+
+fn reset_run_stats(mut run_stats: ResMut<RunStats>) {
+    *run_stats = RunStats::default();
+}
+
+fn count_base_visit(mut run_stats: ResMut<RunStats>) {
+    run_stats.totals.base_visits += 1;
+}
+
+fn track_run_stats(
+    time: Res<Time>,
+    player: Query<(Entity, &Transform, &DrillState, &Fuel, &Health, &Currency), With<Player>>,
+    mut tile_destroyed_events: EventReader<TileDestroyedEvent>,
+    mut damage_events: EventReader<DamageEvent>,
+    mut landing_events: EventReader<LandingEvent>,
+    mut run_stats: ResMut<RunStats>,
+) {
+    if player.is_empty() {
+        return;
+    }
+    let run_stats = &mut *run_stats;
+    let totals = &mut run_stats.totals;
+    totals.time_played += time.delta_secs();
+    // Only solid tiles are dug, the same ones the lifetime totals are saved for
+    for event in tile_destroyed_events
+        .read()
+        .filter(|event| TileType::SOLID.contains(&event.tile_type))
+    {
+        *totals.tiles_dug.entry(event.tile_type).or_default() += 1;
+    }
+    for event in damage_events.read().filter(|e| e.damage_type.is_hit()) {
+        totals.hits_taken += 1;
+        totals.hardest_hit = totals.hardest_hit.max(event.amount);
+    }
+    for event in landing_events.read() {
+        totals.fastest_landing = totals.fastest_landing.max(event.speed);
+    }
+
+    // The crew shares one set of statistics
+    for (entity, transform, drill_state, fuel, health, currency) in player.iter() {
+        let position = transform.translation.truncate();
+        let depth = -world_to_grid_position(position).1;
+        totals.max_depth = totals.max_depth.max(depth);
+        let sample = StatSample {
+            position,
+            fuel: fuel.current,
+            health: health.current,
+            currency: currency.amount,
+        };
+        if let Some(last) = run_stats.last_samples.insert(entity, sample) {
+            let step = position.distance(last.position);
+            if *drill_state == DrillState::Flying && step < MAX_FLIGHT_STEP {
+                totals.distance_flown += step;
+            }
+            totals.fuel_burned += (last.fuel - fuel.current).max(0.0);
+            totals.damage_taken += (last.health - health.current).max(0.0);
+            totals.money_earned += currency.amount.saturating_sub(last.currency);
+            totals.money_spent += last.currency.saturating_sub(currency.amount);
+        }
+    }
+}
+
+fn track_survived_landings(
+    player: Query<&Health, With<Player>>,
+    mut landing_events: EventReader<LandingEvent>,
+    mut run_stats: ResMut<RunStats>,
+) {
+    for event in landing_events.read() {
+        if player.get(event.player).is_ok_and(|health| health.current > 0.0) {
+            let totals = &mut run_stats.totals;
+            totals.survived_landing = totals.survived_landing.max(event.speed);
+        }
+    }
+}
+
+fn finish_run(mut run_stats: ResMut<RunStats>, mut lifetime_stats: ResMut<LifetimeStats>) {
+    run_stats.finished = true;
+    lifetime_stats.runs += 1;
+    lifetime_stats.totals.add(&run_stats.totals);
+    lifetime_stats.save();
+    info!("Run finished: {}", run_stats.totals.describe().join(", "));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lifetime_stats_round_trip() {
+        let mut stats = LifetimeStats {
+            runs: 4,
+            ..default()
+        };
+        let totals = &mut stats.totals;
+        totals.tiles_dug.insert(TileType::Copper, 31);
+        totals.tiles_dug.insert(TileType::Crystal, 2);
+        totals.max_depth = 87;
+        totals.distance_flown = 1234.5;
+        totals.damage_taken = 60.25;
+        totals.hits_taken = 9;
+        totals.hardest_hit = 22.0;
+        totals.fastest_landing = 640.0;
+        totals.survived_landing = 410.0;
+        totals.fuel_burned = 333.0;
+        totals.money_earned = 2500;
+        totals.money_spent = 1900;
+        totals.base_visits = 12;
+        totals.time_played = 1800.5;
+
+        let loaded = LifetimeStats::parse(&stats.to_cfg());
+        assert_eq!(loaded.runs, 4);
+        assert_eq!(loaded.totals.describe(), stats.totals.describe());
+        assert_eq!(loaded.totals.survived_landing, 410.0);
+        assert_eq!(loaded.totals.tiles_dug.get(&TileType::Crystal), Some(&2));
+    }
+
+    #[test]
+    fn bad_lines_are_skipped() {
+        let contents = [
+            "# comment",
+            "runs = 3",
+            "nonsense",
+            "max_depth = deep",
+            "unknown = 1",
+            "tiles_dug_gold = 5",
+        ]
+        .join("\n");
+        let stats = LifetimeStats::parse(&contents);
+        assert_eq!(stats.runs, 3);
+        assert_eq!(stats.totals.max_depth, 0);
+        assert_eq!(stats.totals.tiles_dug.get(&TileType::Gold), Some(&5));
+    }
+
+    #[test]
+    fn adding_totals_sums_counters_and_keeps_records() {
+        let mut lifetime = StatTotals {
+            max_depth: 50,
+            hits_taken: 2,
+            ..default()
+        };
+        let run = StatTotals {
+            max_depth: 30,
+            hits_taken: 3,
+            survived_landing: 200.0,
+            ..default()
+        };
+        lifetime.add(&run);
+        assert_eq!(lifetime.max_depth, 50);
+        assert_eq!(lifetime.hits_taken, 5);
+        assert_eq!(lifetime.survived_landing, 200.0);
+    }
+}
@@ -1,72 +1,268 @@
-use std::ops::Mul;
 use crate::game::GameState;
 use crate::map::TILE_SIZE;
-use crate::player::Player;
+use crate::player::{Player, PlayerId};
 use crate::prelude::GameState::Playing;
 use crate::prelude::GameSystems::Ui;
-use crate::prelude::{GameAssets, Inventory, LoadingProgress, MenuState};
+use crate::prelude::{
+    GameAssets, LoadingProgress, MenuPlayer, MenuState, RemoteInput, simulates_world,
+};
 use bevy::prelude::*;
 use bevy_rapier2d::pipeline::CollisionEvent;
 use bevy_rapier2d::prelude::{ActiveEvents, Collider, Sensor};
+use std::fs;
+
+/// Buildings of the surface settlement, shipped with the game
+const BUILDINGS_FILE: &str = "assets/buildings.cfg";
+/// Height (px) of the ground the buildings stand on
+const SURFACE_Y: f32 = -TILE_SIZE + 7.0;
 
 pub struct WorldBasePlugin;
 
+/// Sensor of the home building: tows, recalls and teleporters bring the drill here
 #[derive(Component)]
 pub struct WorldBase;
 
+/// Sensor of a surface building, index in `Settlement::buildings`
+#[derive(Component, Clone, Copy)]
+pub struct Building(pub usize);
+
+/// What a building offers once a drill drives in
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BuildingService {
+    // Fills the tank
+    Refuel,
+    // Sells the cargo at market price, all at once or from the trading screen
+    Exchange,
+    // Buys and swaps equipment modules
+    Upgrades,
+    // Mends the hull
+    Repair,
+    // Dynamite, consumables and the recall device
+    Shop,
+}
+
+impl BuildingService {
+    fn parse(text: &str) -> Option<Self> {
+        let service = match text {
+            "refuel" => BuildingService::Refuel,
+            "exchange" => BuildingService::Exchange,
+            "upgrades" => BuildingService::Upgrades,
+            "repair" => BuildingService::Repair,
+            "shop" => BuildingService::Shop,
+            _ => return None,
+        };
+        Some(service)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BuildingDefinition {
+    pub id: String,
+    pub name: String,
+    // Horizontal position of the building centre, in tiles from the drop point
+    pub position: f32,
+    pub width: f32,
+    pub tint: Color,
+    pub services: Vec<BuildingService>,
+}
+
+impl BuildingDefinition {
+    pub fn offers(&self, service: BuildingService) -> bool {
+        self.services.contains(&service)
+    }
+}
+
+/// Buildings laid out along the surface, the first one is the home building
+#[derive(Resource)]
+pub struct Settlement {
+    pub buildings: Vec<BuildingDefinition>,
+}
+
+impl Settlement {
+    pub fn load() -> Self {
+        let buildings: Vec<BuildingDefinition> = match fs::read_to_string(BUILDINGS_FILE) {
+            Ok(contents) => contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .filter_map(|line| {
+                    let building = parse_definition(line);
+                    if building.is_none() {
+                        warn!("Ignoring malformed building '{}'", line);
+                    }
+                    building
+                })
+                .collect(),
+            Err(error) => {
+                warn!("Cannot read {}: {}", BUILDINGS_FILE, error);
+                Vec::new()
+            }
+        };
+        if buildings.is_empty() {
+            return Self::default();
+        }
+        for (index, building) in buildings.iter().enumerate() {
+            if buildings[..index].iter().any(|other| other.id == building.id) {
+                warn!("Building id '{}' is used more than once", building.id);
+            }
+        }
+        Self { buildings }
+    }
+}
+
+impl Default for Settlement {
+    /// A single base offering everything, used when the definitions cannot be read
+    fn default() -> Self {
+        Self {
+            buildings: vec![BuildingDefinition {
+                id: "world_base".to_string(),
+                name: "World base".to_string(),
+                position: -6.0,
+                width: 6.0,
+                tint: Color::WHITE,
+                services: vec![
+                    BuildingService::Refuel,
+                    BuildingService::Exchange,
+                    BuildingService::Upgrades,
+                    BuildingService::Repair,
+                    BuildingService::Shop,
+                ],
+            }],
+        }
+    }
+}
+
+/// Reads "id | name | position | width | tint | services"
+fn parse_definition(line: &str) -> Option<BuildingDefinition> {
+    let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+    let [id, name, position, width, tint, services] = fields.as_slice() else {
+        return None;
+    };
+    let tint: Vec<f32> = tint
+        .split(',')
+        .map(|channel| channel.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    let [red, green, blue] = tint.as_slice() else {
+        return None;
+    };
+    Some(BuildingDefinition {
+        id: id.to_string(),
+        name: name.to_string(),
+        position: position.parse().ok()?,
+        width: width.parse::<f32>().ok()?.max(3.0),
+        tint: Color::srgb(*red, *green, *blue),
+        services: services
+            .split(',')
+            .map(|service| BuildingService::parse(service.trim()))
+            .collect::<Option<_>>()?,
+    })
+}
+
+/// Building the drill at the base drove into
+#[derive(Resource, Default)]
+pub struct VisitedBuilding(pub usize);
+
 /// This plugin handles base-related stuff
 impl Plugin for WorldBasePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Rendering), spawn_base)
-            .add_systems(Update, base_access.in_set(Ui).run_if(in_state(Playing)));
+        app.insert_resource(Settlement::load())
+            .init_resource::<VisitedBuilding>()
+            .add_systems(OnEnter(GameState::Rendering), spawn_base)
+            .add_systems(
+                Update,
+                base_access
+                    .in_set(Ui)
+                    .run_if(in_state(Playing).and(simulates_world)),
+            );
     }
 }
+
 fn spawn_base(
     mut commands: Commands,
+    assets_server: Res<AssetServer>,
     game_assets: Res<GameAssets>,
+    settlement: Res<Settlement>,
     mut loading_progress: ResMut<LoadingProgress>,
 ) {
-    // World Base
-    let base_size = TILE_SIZE * 6.0;
-    commands
-        .spawn((
-            Sprite {
-                image: game_assets.buildings.texture.clone(),
-                texture_atlas: Some(TextureAtlas {
-                    layout: game_assets.buildings.texture_layout.clone(),
-                    index: 0,
-                }),
-                custom_size: Some(Vec2::new(base_size,base_size-TILE_SIZE.mul(2.0))),
-                ..default()
-            },
-            Transform::from_xyz(-base_size, (base_size/ 3.0) - TILE_SIZE+7.0, -1.0),
-            ActiveEvents::COLLISION_EVENTS,
-        ))
-        .with_children(|parent| {
-            parent.spawn((
-                WorldBase,
-                Transform::from_xyz(0.0, -((base_size / 2.0) - TILE_SIZE), -1.0),
-                Collider::cuboid(TILE_SIZE / 2f32, TILE_SIZE / 2f32),
-                Sensor,
-            ));
-        });
+    let font = assets_server.load("fonts/FiraSans-Regular.ttf");
+    for (index, building) in settlement.buildings.iter().enumerate() {
+        // Same proportions as the original base: two tiles shorter than wide
+        let size = Vec2::new(building.width, building.width - 2.0) * TILE_SIZE;
+        commands
+            .spawn((
+                Sprite {
+                    image: game_assets.buildings.texture.clone(),
+                    texture_atlas: Some(TextureAtlas {
+                        layout: game_assets.buildings.texture_layout.clone(),
+                        index: 0,
+                    }),
+                    custom_size: Some(size),
+                    color: building.tint,
+                    ..default()
+                },
+                Transform::from_xyz(
+                    building.position * TILE_SIZE,
+                    SURFACE_Y + size.y / 2.0,
+                    -1.0,
+                ),
+                ActiveEvents::COLLISION_EVENTS,
+            ))
+            .with_children(|parent| {
+                let mut sensor = parent.spawn((
+                    Building(index),
+                    Transform::from_xyz(0.0, -size.y / 2.0, -1.0),
+                    Collider::cuboid(TILE_SIZE / 2f32, TILE_SIZE / 2f32),
+                    Sensor,
+                ));
+                if index == 0 {
+                    sensor.insert(WorldBase);
+                }
+                // The buildings share one texture, the sign tells them apart
+                parent.spawn((
+                    Text2d::new(building.name.clone()),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    Transform::from_xyz(0.0, size.y / 2.0 + TILE_SIZE / 2.0, 1.0),
+                ));
+            });
+    }
     loading_progress.spawning_base = true;
 }
 
+#[allow(clippy::too_many_arguments)]
 fn base_access(
     mut collision_events: EventReader<CollisionEvent>,
-    player: Query<&Inventory, With<Player>>,
-    world_base: Query<&Transform, With<WorldBase>>,
+    // Remote drills are serviced by `service_remote_visits` instead
+    player: Query<&PlayerId, (With<Player>, Without<RemoteInput>)>,
+    buildings: Query<&Building>,
+    settlement: Res<Settlement>,
     mut next_game_state: ResMut<NextState<GameState>>,
     mut next_menu_state: ResMut<NextState<MenuState>>,
+    mut menu_player: ResMut<MenuPlayer>,
+    mut visited_building: ResMut<VisitedBuilding>,
 ) {
     for event in collision_events.read() {
         match event {
             CollisionEvent::Started(collider1, collider2, _) => {
-                if (player.get(*collider1).is_ok() && world_base.get(*collider2).is_ok())
-                    || (player.get(*collider2).is_ok() && world_base.get(*collider1).is_ok())
-                {
-                    info!("Player has accessed the base");
+                let (building, player_id) = if let Ok(building) = buildings.get(*collider2) {
+                    (building, player.get(*collider1))
+                } else if let Ok(building) = buildings.get(*collider1) {
+                    (building, player.get(*collider2))
+                } else {
+                    continue;
+                };
+                if let Ok(player_id) = player_id {
+                    info!(
+                        "{} has accessed the {}",
+                        player_id.name(),
+                        settlement.buildings[building.0].name
+                    );
+                    menu_player.0 = *player_id;
+                    visited_building.0 = building.0;
                     next_game_state.set(GameState::Menu);
                     next_menu_state.set(MenuState::WorldBase);
                 };
@@ -75,3 +271,56 @@ fn base_access(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_building() {
+        let line = "ore_exchange | Ore Exchange | -13 | 6 | 1.0,0.9,0.5 | exchange, repair";
+        let building = parse_definition(line).unwrap();
+        assert_eq!(building.id, "ore_exchange");
+        assert_eq!(building.name, "Ore Exchange");
+        assert_eq!(building.position, -13.0);
+        assert_eq!(building.width, 6.0);
+        assert_eq!(building.tint, Color::srgb(1.0, 0.9, 0.5));
+        assert_eq!(
+            building.services,
+            vec![BuildingService::Exchange, BuildingService::Repair]
+        );
+    }
+
+    #[test]
+    fn narrow_buildings_are_widened() {
+        let building = parse_definition("hut | Hut | 0 | 1 | 1,1,1 | refuel").unwrap();
+        assert_eq!(building.width, 3.0);
+    }
+
+    #[test]
+    fn malformed_buildings_are_rejected() {
+        assert!(parse_definition("hut | Hut | 0 | 4 | 1,1,1").is_none());
+        assert!(parse_definition("hut | Hut | left | 4 | 1,1,1 | refuel").is_none());
+        assert!(parse_definition("hut | Hut | 0 | 4 | 1,1 | refuel").is_none());
+        assert!(parse_definition("hut | Hut | 0 | 4 | 1,1,1 | casino").is_none());
+    }
+
+    #[test]
+    fn shipped_settlement_offers_every_service() {
+        let settlement = Settlement::load();
+        assert_eq!(settlement.buildings.len(), 5);
+        for service in [
+            BuildingService::Refuel,
+            BuildingService::Exchange,
+            BuildingService::Upgrades,
+            BuildingService::Repair,
+            BuildingService::Shop,
+        ] {
+            assert!(
+                settlement.buildings.iter().any(|b| b.offers(service)),
+                "no building offers {:?}",
+                service
+            );
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use crate::game::GameState;
+use crate::map::{MapSeed, TILE_SIZE, TileType};
+use crate::menu::{MenuPlayer, MenuState};
+use crate::player::{Consumable, Inventory, Item, Player, PlayerId};
+use crate::prelude::GameState::Playing;
+use crate::prelude::GameSystems::{Rendering, Running};
+use crate::prelude::{GameAssets, simulates_world};
+use bevy::prelude::*;
+use std::fs;
+use std::io::ErrorKind;
+
+/// File the pending wreck is loaded from and saved to, next to the game
+const WRECK_FILE: &str = "wreck.cfg";
+
+pub struct WreckPlugin;
+
+/// Remains of the drill from a previous run, waiting to be salvaged
+#[derive(Component)]
+pub struct Salvage;
+
+pub struct Wreck {
+    // Seed of the map the drill was destroyed in, the wreck only exists in that world
+    pub seed: u64,
+    pub position: Vec2,
+    pub items: Vec<Item>,
+}
+
+/// Last wreck left behind, carried over to the next run
+#[derive(Resource, Default)]
+pub struct WreckSite {
+    pub wreck: Option<Wreck>,
+}
+
+impl WreckSite {
+    pub fn load() -> Self {
+        let Ok(contents) = fs::read_to_string(WRECK_FILE) else {
+            return Self::default();
+        };
+        Self::parse(&contents)
+    }
+
+    fn parse(contents: &str) -> Self {
+        let (mut seed, mut x, mut y) = (None, None, None);
+        let mut items = Vec::new();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                warn!("Ignoring malformed wreck line '{}'", line);
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            match key {
+                "seed" => seed = value.parse::<u64>().ok(),
+                "x" => x = value.parse::<f32>().ok(),
+                "y" => y = value.parse::<f32>().ok(),
+                _ => {
+                    let item = key.strip_prefix("item_").and_then(item_from_id);
+                    match (item, value.parse::<usize>()) {
+                        (Some(item), Ok(quantity)) if quantity > 0 => {
+                            items.push(Item { quantity, ..item })
+                        }
+                        _ => warn!("Ignoring wreck item '{}' with value '{}'", key, value),
+                    }
+                }
+            }
+        }
+        let (Some(seed), Some(x), Some(y)) = (seed, x, y) else {
+            warn!("Ignoring incomplete wreck in {}", WRECK_FILE);
+            return Self::default();
+        };
+        if items.is_empty() {
+            return Self::default();
+        }
+        info!("Wreck waiting at ({}, {}) on seed {}", x, y, seed);
+        Self {
+            wreck: Some(Wreck {
+                seed,
+                position: Vec2::new(x, y),
+                items,
+            }),
+        }
+    }
+
+    /// Writes the pending wreck, or removes the file once there is nothing left to salvage
+    pub fn save(&self) {
+        let Some(wreck) = &self.wreck else {
+            if let Err(error) = fs::remove_file(WRECK_FILE) {
+                if error.kind() != ErrorKind::NotFound {
+                    warn!("Cannot remove {}: {}", WRECK_FILE, error);
+                }
+            }
+            return;
+        };
+        if let Err(error) = fs::write(WRECK_FILE, wreck.to_cfg()) {
+            warn!("Cannot save the wreck to {}: {}", WRECK_FILE, error);
+        }
+    }
+}
+
+impl Wreck {
+    fn to_cfg(&self) -> String {
+        let mut contents = String::from("# Wreck left by the last run\n");
+        contents.push_str(&format!("seed = {}\n", self.seed));
+        contents.push_str(&format!("x = {}\n", self.position.x));
+        contents.push_str(&format!("y = {}\n", self.position.y));
+        for item in &self.items {
+            contents.push_str(&format!("item_{} = {}\n", item.id, item.quantity));
+        }
+        contents
+    }
+}
+
+/// Single unit of the loot or consumable with the given id
+fn item_from_id(id: &str) -> Option<Item> {
+    TileType::SOLID
+        .into_iter()
+        .filter_map(|tile_type| tile_type.to_item())
+        .chain(Consumable::ALL.map(Consumable::to_item))
+        .find(|item| item.id == id)
+}
+
+impl Plugin for WreckPlugin {
+    fn build(&self, app: &mut App) {
+        let wreck_site = WreckSite::load();
+        // The first run goes back to the world of a wreck left in an earlier session
+        if let Some(wreck) = &wreck_site.wreck {
+            app.insert_resource(MapSeed(wreck.seed));
+        }
+        app.insert_resource(wreck_site)
+            .add_systems(
+                OnEnter(MenuState::GameOver),
+                (record_wreck, roll_map_seed).chain(),
+            )
+            .add_systems(OnEnter(GameState::Rendering), spawn_wreck.in_set(Rendering))
+            .add_systems(Update, salvage_wreck.in_set(Running).run_if(in_state(Playing).and(simulates_world)));
+    }
+}
+
+fn record_wreck(
+    player: Query<(&PlayerId, &Transform, &Inventory), With<Player>>,
+    menu_player: Res<MenuPlayer>,
+    map_seed: Res<MapSeed>,
+    mut wreck_site: ResMut<WreckSite>,
+) {
+    // The wreck is the drill that ended the run
+    if let Some((_, transform, inventory)) = player
+        .iter()
+        .find(|(player_id, ..)| **player_id == menu_player.0)
+    {
+        if inventory.items.is_empty() {
+            return;
+        }
+        info!(
+            "Drill wrecked at {:?} with {}",
+            transform.translation.truncate(),
+            inventory.print_items()
+        );
+        wreck_site.wreck = Some(Wreck {
+            seed: map_seed.0,
+            position: transform.translation.truncate(),
+            items: inventory.items.clone(),
+        });
+        wreck_site.save();
+    }
+}
+
+/// The next run only goes back to the same world while a wreck is waiting there
+fn roll_map_seed(wreck_site: Res<WreckSite>, mut map_seed: ResMut<MapSeed>) {
+    *map_seed = match &wreck_site.wreck {
+        Some(wreck) => MapSeed(wreck.seed),
+        None => MapSeed::default(),
+    };
+}
+
+fn spawn_wreck(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    map_seed: Res<MapSeed>,
+    wreck_site: Res<WreckSite>,
+    salvage: Query<Entity, With<Salvage>>,
+) {
+    for entity in salvage.iter() {
+        commands.entity(entity).despawn();
+    }
+    let Some(wreck) = wreck_site.wreck.as_ref().filter(|w| w.seed == map_seed.0) else {
+        return;
+    };
+    commands.spawn((
+        Salvage,
+        Sprite {
+            image: game_assets.player.texture.clone(),
+            texture_atlas: Some(TextureAtlas {
+                layout: game_assets.player.texture_layout.clone(),
+                index: 0,
+            }),
+            color: Color::srgb(0.4, 0.4, 0.4),
+            custom_size: Some(Vec2::splat(TILE_SIZE)),
+            ..default()
+        },
+        Transform::from_translation(wreck.position.extend(0.5)),
+    ));
+}
+
+fn salvage_wreck(
+    mut commands: Commands,
+    mut player: Query<(&Transform, &mut Inventory), With<Player>>,
+    salvage: Query<(Entity, &Transform), With<Salvage>>,
+    mut wreck_site: ResMut<WreckSite>,
+) {
+    let Ok((entity, salvage_transform)) = salvage.single() else {
+        return;
+    };
+    let salvage_position = salvage_transform.translation.truncate();
+    let Some((_, mut inventory)) = player.iter_mut().find(|(player_transform, _)| {
+        player_transform.translation.truncate().distance(salvage_position) <= TILE_SIZE
+    }) else {
+        return;
+    };
+    let Some(wreck) = wreck_site.wreck.as_mut() else {
+        return;
+    };
+    // Take as much as the hold allows, the rest stays in the wreck
+    let mut recovered = 0;
+    for item in wreck.items.iter_mut() {
+        while item.quantity > 0 && inventory.has_room_for(1) {
+            inventory.add_item(Item {
+                quantity: 1,
+                ..item.clone()
+            });
+            item.quantity -= 1;
+            recovered += 1;
+        }
+    }
+    wreck.items.retain(|item| item.quantity > 0);
+    if recovered == 0 {
+        return;
+    }
+    info!("Salvaged {} items from the wreck", recovered);
+    if wreck.items.is_empty() {
+        info!("Wreck fully salvaged");
+        wreck_site.wreck = None;
+        commands.entity(entity).despawn();
+    }
+    wreck_site.save();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wreck_round_trips() {
+        let wreck = Wreck {
+            seed: 9_876_543_210_123,
+            position: Vec2::new(-48.5, -1024.0),
+            items: vec![
+                Item {
+                    quantity: 3,
+                    ..TileType::Gold.to_item().unwrap()
+                },
+                Item {
+                    quantity: 2,
+                    ..Consumable::RepairKit.to_item()
+                },
+            ],
+        };
+        let loaded = WreckSite::parse(&wreck.to_cfg()).wreck.unwrap();
+        assert_eq!(loaded.seed, wreck.seed);
+        assert_eq!(loaded.position, wreck.position);
+        let items: Vec<(&str, usize, Option<Consumable>)> = loaded
+            .items
+            .iter()
+            .map(|item| (item.id.as_str(), item.quantity, item.consumable))
+            .collect();
+        assert_eq!(
+            items,
+            vec![
+                ("gold", 3, None),
+                ("repair_kit", 2, Some(Consumable::RepairKit))
+            ]
+        );
+    }
+
+    #[test]
+    fn incomplete_or_empty_wrecks_are_ignored() {
+        assert!(WreckSite::parse("seed = 1\nx = 0\nitem_iron = 2\n").wreck.is_none());
+        assert!(WreckSite::parse("seed = 1\nx = 0\ny = 0\n").wreck.is_none());
+        assert!(
+            WreckSite::parse("seed = 1\nx = 0\ny = 0\nitem_unobtainium = 2\nitem_iron = 0\n")
+                .wreck
+                .is_none()
+        );
+    }
+}